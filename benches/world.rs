@@ -0,0 +1,109 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use dotrix::world::World;
+
+const ENTITIES: usize = 100_000;
+
+struct Position(f32, f32, f32);
+struct Velocity(f32, f32, f32);
+struct Health(u32);
+
+fn entities() -> impl Iterator<Item = (Position, Velocity, Health)> {
+    (0..ENTITIES).map(|i| {
+        let value = i as f32;
+        (
+            Position(value, value, value),
+            Velocity(1.0, 0.5, 0.25),
+            Health(100),
+        )
+    })
+}
+
+fn spawn(criterion: &mut Criterion) {
+    criterion.bench_function("spawn 100k", |bencher| {
+        bencher.iter_batched(
+            World::new,
+            |mut world| {
+                world.spawn(entities()).count();
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn query(criterion: &mut Criterion) {
+    let mut world = World::new();
+    world.spawn(entities()).count();
+
+    criterion.bench_function("query 100k", |bencher| {
+        bencher.iter(|| {
+            for (position, velocity) in world.query::<(&mut Position, &Velocity)>() {
+                position.0 += velocity.0;
+                position.1 += velocity.1;
+                position.2 += velocity.2;
+            }
+        })
+    });
+
+    criterion.bench_function("query read 100k", |bencher| {
+        bencher.iter(|| {
+            black_box(
+                world
+                    .query::<(&Health,)>()
+                    .map(|(health,)| health.0 as u64)
+                    .sum::<u64>(),
+            )
+        })
+    });
+}
+
+fn exile(criterion: &mut Criterion) {
+    criterion.bench_function("exile 100k", |bencher| {
+        bencher.iter_batched(
+            || {
+                let mut world = World::new();
+                let ids = world.spawn(entities()).collect::<Vec<_>>();
+                (world, ids)
+            },
+            |(mut world, ids)| {
+                for id in ids.iter() {
+                    black_box(world.exile(id));
+                }
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn lookup(criterion: &mut Criterion) {
+    let mut world = World::new();
+    let ids = world.spawn(entities()).collect::<Vec<_>>();
+    let handles = ids
+        .iter()
+        .map(|id| world.handle(id).unwrap())
+        .collect::<Vec<_>>();
+
+    criterion.bench_function("lookup by id 100k", |bencher| {
+        bencher.iter(|| {
+            for id in ids.iter() {
+                black_box(world.component_mut::<Health>(id).map(|health| health.0));
+            }
+        })
+    });
+
+    criterion.bench_function("lookup by handle 100k", |bencher| {
+        bencher.iter(|| {
+            for handle in handles.iter() {
+                black_box(
+                    world
+                        .component_mut::<Health>(*handle)
+                        .map(|health| health.0),
+                );
+            }
+        })
+    });
+}
+
+criterion_group!(benches, spawn, query, exile, lookup);
+criterion_main!(benches);
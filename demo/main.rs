@@ -55,10 +55,15 @@ impl dotrix::Application for Demo {
         scheduler.add_context(dotrix::Assets::default());
         // add World context
         scheduler.add_context(dotrix::World::default());
+        // add Camera context
+        scheduler.add_context(dotrix::Camera::new(
+            dotrix::Camera::lens(std::f32::consts::FRAC_PI_4, 1.0..10.0),
+            dotrix::Camera::at(1.5, -5.0, 3.0).target(dotrix::math::Vec3::ZERO),
+        ));
         // add spawner tasks
         scheduler.add_task(scene::SpawnEntities::default());
-        // add rendering task
-        scheduler.add_task(renderer);
+        // add rendering tasks
+        dotrix::models::add_render_tasks(scheduler, renderer);
     }
 }
 
@@ -57,6 +57,11 @@ impl dotrix::Task for SpawnEntities {
                 .last()
                 .expect("The entity must be spawned");
 
+            world.spawn_and_count([
+                (dotrix::Light::ambient(-1.0, 1.0, -2.0).intensity(0.8),),
+                (dotrix::Light::point(10.0, 10.0, 10.0).intensity(0.6),),
+            ]);
+
             log::info!("Spawn scene entities: {:?}", self.spawned);
         }
         Scene
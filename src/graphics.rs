@@ -8,7 +8,9 @@ use crate::window;
 
 pub use formats::Extent2D;
 pub use frame::{CreateFrame, Frame, RenderPass, SubmitFrame};
-pub use vulkan::{Buffer, CommandBufferIter, Display, FramePresenter, Gpu, Semaphore, Surface};
+pub use vulkan::{
+    Buffer, CommandBufferIter, Display, FramePresenter, Gpu, Semaphore, Surface, Texture,
+};
 
 /// GPU device type
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -20,8 +22,10 @@ pub enum DeviceType {
 }
 
 pub struct DisplaySetup<'a> {
-    /// Window reference
-    pub window_instance: window::Instance,
+    /// Window reference, headless display is created if `None`
+    pub window_instance: Option<window::Instance>,
+    /// Resolution of the headless display
+    pub resolution: Extent2D,
     /// Application name
     pub app_name: &'a str,
     /// Application version
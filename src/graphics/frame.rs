@@ -1,6 +1,7 @@
 use super::{Display, Extent2D, FramePresenter};
 use crate::log;
-use crate::tasks::{All, Any, Mut, OutputChannel, Ref, Task};
+use crate::tasks::{All, Any, Mut, OutputChannel, Ref, Take, Task};
+use crate::window::ResizeRequest;
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -23,12 +24,16 @@ pub struct Frame {
     pub scale_factor: f32,
     /// Shows if frame was resized
     pub resized: bool,
+    /// Shows if there is no image to render the frame into, e.g. window is minimized
+    pub skipped: bool,
 }
 
 /// Task, responsible for frame creation
 pub struct CreateFrame {
     frame_counter: u64,
     fps_request: Option<f32>,
+    fixed_timestep: Option<Duration>,
+    recorded_deltas: VecDeque<Duration>,
     log_fps_interval: Option<Duration>,
     log_fps_timestamp: Instant,
     last_frame: Option<Instant>,
@@ -40,6 +45,8 @@ impl Default for CreateFrame {
         Self {
             frame_counter: 0,
             fps_request: None,
+            fixed_timestep: None,
+            recorded_deltas: VecDeque::new(),
             log_fps_interval: None,
             log_fps_timestamp: Instant::now(),
             last_frame: None,
@@ -60,40 +67,42 @@ impl CreateFrame {
         self.fps_request = fps_request;
         self
     }
-}
 
-impl Task for CreateFrame {
-    type Context = (Mut<Display>,);
-    type Output = Frame;
+    /// Sets fixed duration of frames, reported as `Frame::delta` regardless of the real time
+    pub fn fixed_timestep(mut self, fixed_timestep: Option<Duration>) -> Self {
+        self.fixed_timestep = fixed_timestep;
+        self
+    }
 
-    fn run(&mut self, (mut display,): Self::Context) -> Self::Output {
-        log::debug!("CreateFrame::run() -> begin");
-        let frame_number = self.frame_counter + 1;
-        let now = Instant::now();
-        let delta = self
-            .last_frame
-            .replace(now)
-            .map(|i| i.elapsed())
-            .unwrap_or_else(|| Duration::from_secs_f32(1.0 / self.fps_request.unwrap_or(60.0)));
+    /// Sets durations of the first frames, e.g. of a replayed recording
+    ///
+    /// They take precedence over the fixed timestep, later frames are timed as usual.
+    pub fn recorded_deltas(mut self, deltas: impl IntoIterator<Item = Duration>) -> Self {
+        self.recorded_deltas = deltas.into_iter().collect();
+        self
+    }
 
-        log::debug!("CreateFrame::run() -> delta: {:?}", delta);
-        // resize surface before aquiring of the new frame
-        let mut resized = false;
-        log::debug!("CreateFrame::run() -> display.surface_resize_request()");
-        // NOTE: on iOS winit.inner_size() is not possible to use in a thread. We need a different
-        // surface size / resize solution
-        // if display.surface_resize_request() {
-        if self.last_frame.is_none() {
-            log::debug!("surface resized");
-            display.resize_surface();
-            resized = true;
-        }
-        log::debug!("CreateFrame::run() -> display.surface_resolution()");
-        let surface_resolution = display.surface_resolution();
+    /// Returns duration of the next frame
+    fn next_delta(&mut self, now: Instant) -> Duration {
+        let elapsed = self.last_frame.replace(now).map(|i| now - i);
+        self.recorded_deltas
+            .pop_front()
+            .or(self.fixed_timestep)
+            .or(elapsed)
+            .unwrap_or_else(|| Duration::from_secs_f32(1.0 / self.fps_request.unwrap_or(60.0)))
+    }
 
-        log::debug!("CreateFrame::run() -> begin display.next_frame()");
-        let swapchain_index = display.next_frame();
-        log::debug!("CreateFrame::run() -> end display.next_frame()");
+    /// Creates the next frame, `None` swapchain index means that the frame is skipped
+    fn create(
+        &mut self,
+        now: Instant,
+        swapchain_index: Option<u32>,
+        resolution: Extent2D,
+        resized: bool,
+    ) -> Frame {
+        let frame_number = self.frame_counter + 1;
+        let delta = self.next_delta(now);
+        log::debug!("CreateFrame::run() -> delta: {:?}", delta);
         // TODO: scale factor comes from a window, so shall it be taken from `Window` instance?
         let scale_factor = 1.0;
 
@@ -118,16 +127,53 @@ impl Task for CreateFrame {
             }
         }
 
-        let frame = Frame {
+        Frame {
             fps,
             delta,
             timestamp: now,
-            resolution: surface_resolution,
+            resolution,
             number: frame_number,
-            swapchain_index,
+            swapchain_index: swapchain_index.unwrap_or(0),
             scale_factor,
             resized,
-        };
+            skipped: swapchain_index.is_none(),
+        }
+    }
+}
+
+impl Task for CreateFrame {
+    type Context = (Mut<Display>, Take<All<ResizeRequest>>);
+    type Output = Frame;
+
+    fn run(&mut self, (mut display, resize_requests): Self::Context) -> Self::Output {
+        log::debug!("CreateFrame::run() -> begin");
+        let now = Instant::now();
+
+        // NOTE: on iOS winit.inner_size() is not possible to use in a thread, so the size comes
+        // from the event loop
+        if let Some(request) = resize_requests.take().pop() {
+            display.request_resize(Extent2D {
+                width: request.width,
+                height: request.height,
+            });
+        }
+
+        // resize surface before aquiring of the new frame
+        let surface_version = display.surface_version();
+        log::debug!("CreateFrame::run() -> display.surface_resize_request()");
+        if display.surface_resize_request() {
+            log::debug!("surface resized");
+            display.resize_surface();
+        }
+        let resized = display.surface_version() != surface_version;
+        log::debug!("CreateFrame::run() -> display.surface_resolution()");
+        let surface_resolution = display.surface_resolution();
+
+        log::debug!("CreateFrame::run() -> begin display.next_frame()");
+        let next_frame = display.next_frame();
+        log::debug!("CreateFrame::run() -> end display.next_frame()");
+
+        let frame = self.create(now, next_frame, surface_resolution, resized);
         log::debug!("CreateFrame::run() -> {:?}", frame);
         frame
     }
@@ -146,10 +192,58 @@ impl Task for SubmitFrame {
     }
 
     fn run(&mut self, (display, frame, _): Self::Context) -> Self::Output {
+        if frame.skipped {
+            return display.presenter(None);
+        }
+        display.serve_frame_requests(frame.swapchain_index);
         log::info!("get presenter");
-        display.presenter(frame.swapchain_index)
+        display.presenter(Some(frame.swapchain_index))
     }
 }
 
 /// Render Pass Output
 pub struct RenderPass {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::CreateFrame;
+    use crate::graphics::Extent2D;
+
+    #[test]
+    fn recorded_deltas_take_precedence() {
+        let fixed_timestep = Duration::from_millis(16);
+        let recorded = [Duration::from_millis(10), Duration::from_millis(40)];
+        let mut create_frame = CreateFrame::default()
+            .fixed_timestep(Some(fixed_timestep))
+            .recorded_deltas(recorded);
+
+        let now = Instant::now();
+        let deltas = (0..3)
+            .map(|i| create_frame.next_delta(now + Duration::from_millis(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(deltas, vec![recorded[0], recorded[1], fixed_timestep]);
+    }
+
+    #[test]
+    fn frames_without_image_are_skipped() {
+        let resolution = Extent2D {
+            width: 800,
+            height: 600,
+        };
+        let mut create_frame = CreateFrame::default();
+        let now = Instant::now();
+
+        let frame = create_frame.create(now, Some(2), resolution, false);
+        assert!(!frame.skipped);
+        assert_eq!(frame.swapchain_index, 2);
+
+        // e.g. the window is minimized or the swapchain is out of date
+        let frame = create_frame.create(now + Duration::from_millis(16), None, resolution, true);
+        assert!(frame.skipped);
+        assert!(frame.resized);
+        assert_eq!(frame.number, 2);
+        assert_eq!(frame.delta, Duration::from_millis(16));
+    }
+}
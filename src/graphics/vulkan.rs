@@ -1,11 +1,13 @@
 use std::borrow::Cow;
 use std::ffi::{c_char, CStr, CString};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 
 pub use ash::vk;
 use ash::vk::DescriptorPool;
 
 use crate::log;
+use crate::models::Image;
 use crate::window;
 
 use super::{DeviceType, DisplaySetup, Extent2D};
@@ -23,7 +25,7 @@ struct Device {
     vk_queue: vk::Queue,
     vk_device: ash::Device,
     vk_instance: ash::Instance,
-    _vk_entry: ash::Entry,
+    vk_entry: ash::Entry,
 
     vk_debug: Option<(ash::ext::debug_utils::Instance, vk::DebugUtilsMessengerEXT)>,
 }
@@ -52,6 +54,7 @@ pub struct Surface {
     vk_surface_transform: vk::SurfaceTransformFlagsKHR,
     vk_present_mode: vk::PresentModeKHR,
     vk_surface_resolution: vk::Extent2D,
+    vk_physical_device: vk::PhysicalDevice,
     images_count: u32,
     version: u64,
 }
@@ -106,6 +109,7 @@ impl Surface {
             vk_surface_transform: vk::SurfaceTransformFlagsKHR::default(),
             vk_present_mode: vk::PresentModeKHR::default(),
             vk_surface_resolution,
+            vk_physical_device: vk::PhysicalDevice::null(),
             images_count: 2,
             version: 1,
         }
@@ -167,9 +171,41 @@ impl Surface {
         self.vk_present_mode = vk_present_mode;
         self.images_count = images_count;
         self.vk_surface_resolution = vk_surface_resolution;
+        self.vk_physical_device = p_device;
         self.images_count = images_count;
     }
 
+    /// Replaces the lost surface by a new one of the same window
+    ///
+    /// # Safety
+    ///
+    /// Swapchain of the lost surface must be destroyed
+    unsafe fn recreate(
+        &mut self,
+        window: &window::Instance,
+        vk_instance: &ash::Instance,
+        vk_entry: &ash::Entry,
+    ) {
+        self.loader.destroy_surface(self.vk_surface, None);
+        self.vk_surface = Self::new(window, vk_instance, vk_entry).vk_surface;
+        self.vk_surface_resolution = {
+            let resolution = window.resolution();
+            vk::Extent2D {
+                width: resolution.width,
+                height: resolution.height,
+            }
+        };
+        self.configure(self.vk_physical_device);
+    }
+
+    /// Queries current capabilities of the surface, they change with the window size
+    unsafe fn update_capabilities(&mut self) {
+        self.vk_surface_capabilities = self
+            .loader
+            .get_physical_device_surface_capabilities(self.vk_physical_device, self.vk_surface)
+            .expect("Failed to get surface capabilities");
+    }
+
     unsafe fn get_physical_device_support(
         &self,
         p_device: vk::PhysicalDevice,
@@ -184,19 +220,24 @@ impl Surface {
 struct Swapchain {
     loader: ash::khr::swapchain::Device,
     vk_swapchain: vk::SwapchainKHR,
-    _vk_present_images: Vec<vk::Image>,
+    vk_present_images: Vec<vk::Image>,
     vk_present_image_views: Vec<vk::ImageView>,
 }
 
 impl Swapchain {
     unsafe fn create(gpu: &Gpu, surface: &Surface) -> Swapchain {
+        // NOTE: transfer is required to read frames back
+        let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | (surface.vk_surface_capabilities.supported_usage_flags
+                & vk::ImageUsageFlags::TRANSFER_SRC);
+
         let swapchain_create_info = vk::SwapchainCreateInfoKHR::default()
             .surface(surface.vk_surface)
             .min_image_count(surface.images_count)
             .image_color_space(surface.vk_surface_format.color_space)
             .image_format(surface.vk_surface_format.format)
             .image_extent(surface.vk_surface_resolution)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
             .pre_transform(surface.vk_surface_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
@@ -241,7 +282,7 @@ impl Swapchain {
         Swapchain {
             loader,
             vk_swapchain,
-            _vk_present_images: vk_present_images,
+            vk_present_images,
             vk_present_image_views,
         }
     }
@@ -255,6 +296,92 @@ impl Swapchain {
     }
 }
 
+/// Offscreen images of the headless display
+struct Offscreen {
+    images: Vec<Texture>,
+    format: vk::Format,
+    resolution: Extent2D,
+    next_index: AtomicU32,
+    version: u64,
+}
+
+impl Offscreen {
+    unsafe fn create(gpu: &Gpu, resolution: Extent2D, images_count: u32) -> Self {
+        let format = vk::Format::R8G8B8A8_SRGB;
+        let images = (0..images_count)
+            .map(|_| {
+                Texture::create_and_allocate(
+                    gpu,
+                    resolution,
+                    format,
+                    vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+                )
+                .expect("Failed to create an offscreen image")
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            images,
+            format,
+            resolution,
+            next_index: AtomicU32::new(0),
+            version: 1,
+        }
+    }
+
+    unsafe fn destroy(&self, gpu: &Gpu) {
+        for image in self.images.iter() {
+            image.free_memory_and_destroy(gpu);
+        }
+    }
+}
+
+/// Render target of the display
+enum Target {
+    /// Window surface and swapchain
+    Window {
+        window: window::Instance,
+        surface: Surface,
+        swapchain: Arc<Swapchain>,
+    },
+    /// Offscreen images, used in headless mode
+    Offscreen(Offscreen),
+}
+
+impl Target {
+    fn format(&self) -> vk::Format {
+        match self {
+            Target::Window { surface, .. } => surface.vk_surface_format.format,
+            Target::Offscreen(offscreen) => offscreen.format,
+        }
+    }
+
+    fn vk_resolution(&self) -> vk::Extent2D {
+        match self {
+            Target::Window { surface, .. } => surface.vk_surface_resolution,
+            Target::Offscreen(offscreen) => vk::Extent2D {
+                width: offscreen.resolution.width,
+                height: offscreen.resolution.height,
+            },
+        }
+    }
+
+    fn resolution(&self) -> Extent2D {
+        let resolution = self.vk_resolution();
+        Extent2D {
+            width: resolution.width,
+            height: resolution.height,
+        }
+    }
+
+    fn version(&self) -> u64 {
+        match self {
+            Target::Window { surface, .. } => surface.version,
+            Target::Offscreen(offscreen) => offscreen.version,
+        }
+    }
+}
+
 /*
 pub struct Framebuffers {
     vk_framebuffers: Vec<vk::Framebuffer>,
@@ -471,6 +598,25 @@ impl Gpu {
         self.device.vk_device.destroy_image(image, None)
     }
 
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    #[inline(always)]
+    pub unsafe fn create_sampler(
+        &self,
+        create_info: &vk::SamplerCreateInfo,
+    ) -> Result<vk::Sampler, vk::Result> {
+        self.device.vk_device.create_sampler(create_info, None)
+    }
+
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    #[inline(always)]
+    pub unsafe fn destroy_sampler(&self, sampler: vk::Sampler) {
+        self.device.vk_device.destroy_sampler(sampler, None)
+    }
+
     /// # Safety
     ///
     /// This function requires valid Vulkan entities
@@ -649,6 +795,14 @@ impl Gpu {
         self.device.vk_device.destroy_render_pass(render_pass, None);
     }
 
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    #[inline(always)]
+    pub unsafe fn queue_wait_idle(&self) -> Result<(), vk::Result> {
+        self.device.vk_device.queue_wait_idle(self.device.vk_queue)
+    }
+
     /// # Safety
     ///
     /// This function requires valid Vulkan entities
@@ -1010,6 +1164,47 @@ impl Gpu {
         );
     }
 
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    pub unsafe fn cmd_copy_buffer_to_image(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        src_buffer: vk::Buffer,
+        dst_image: vk::Image,
+        dst_image_layout: vk::ImageLayout,
+        regions: &[vk::BufferImageCopy],
+    ) {
+        self.device.vk_device.cmd_copy_buffer_to_image(
+            command_buffer,
+            src_buffer,
+            dst_image,
+            dst_image_layout,
+            regions,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    #[inline(always)]
+    pub unsafe fn cmd_copy_image_to_buffer(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        src_image: vk::Image,
+        src_image_layout: vk::ImageLayout,
+        dst_buffer: vk::Buffer,
+        regions: &[vk::BufferImageCopy],
+    ) {
+        self.device.vk_device.cmd_copy_image_to_buffer(
+            command_buffer,
+            src_image,
+            src_image_layout,
+            dst_buffer,
+            regions,
+        )
+    }
+
     /// # Safety
     ///
     /// This function requires valid Vulkan entities
@@ -1106,14 +1301,19 @@ impl From<CommandBufferIter> for (vk::CommandBuffer, vk::CommandBuffer, vk::Comm
     }
 }
 
+/// Number of offscreen images in headless mode
+const OFFSCREEN_IMAGES_COUNT: u32 = 2;
+
 /// Display abstraction layer
 pub struct Display {
     gpu: Gpu,
-    swapchain: Arc<Swapchain>,
-    surface: Surface,
-    window: window::Instance,
+    target: Target,
     present_complete_semaphore: vk::Semaphore,
     render_complete_semaphore: vk::Semaphore,
+    frame_requests: Mutex<Vec<mpsc::Sender<Image>>>,
+    surface_outdated: Arc<AtomicBool>,
+    surface_lost: Arc<AtomicBool>,
+    requested_resolution: Option<Extent2D>,
     depth_image: vk::Image,
     depth_image_view: vk::ImageView,
     depth_image_memory: vk::DeviceMemory,
@@ -1134,11 +1334,15 @@ impl Drop for Display {
             self.gpu.destroy_semaphore(self.present_complete_semaphore);
             // NOTE: render_complete_semaphore is not owned
 
-            Swapchain::destroy(&self.swapchain, &self.gpu);
-
-            self.surface
-                .loader
-                .destroy_surface(self.surface.vk_surface, None);
+            match &self.target {
+                Target::Window {
+                    surface, swapchain, ..
+                } => {
+                    Swapchain::destroy(swapchain, &self.gpu);
+                    surface.loader.destroy_surface(surface.vk_surface, None);
+                }
+                Target::Offscreen(offscreen) => offscreen.destroy(&self.gpu),
+            }
         }
     }
 }
@@ -1176,19 +1380,33 @@ impl Display {
 
         let window = desc.window_instance;
 
-        let mut surface = unsafe { Surface::new(&window, &vk_instance, &vk_entry) };
+        let mut surface = window
+            .as_ref()
+            .map(|window| unsafe { Surface::new(window, &vk_instance, &vk_entry) });
 
         let (physical_device, queue_family_index) = unsafe {
-            Self::select_device(&vk_instance, &surface, desc.device_type_request.as_ref())
+            Self::select_device(
+                &vk_instance,
+                surface.as_ref(),
+                desc.device_type_request.as_ref(),
+            )
         };
 
-        surface.configure(physical_device);
+        if let Some(surface) = surface.as_mut() {
+            surface.configure(physical_device);
+        }
 
         let features = vk::PhysicalDeviceFeatures {
             shader_clip_distance: 1,
             vertex_pipeline_stores_and_atomics: 1,
             ..Default::default()
         };
+        // bindless textures
+        let mut features_vulkan_12 = vk::PhysicalDeviceVulkan12Features {
+            runtime_descriptor_array: 1,
+            shader_sampled_image_array_non_uniform_indexing: 1,
+            ..Default::default()
+        };
         let priorities = [1.0];
 
         let queue_info = vk::DeviceQueueCreateInfo::default()
@@ -1198,16 +1416,18 @@ impl Display {
         let device_memory_properties =
             unsafe { vk_instance.get_physical_device_memory_properties(physical_device) };
 
-        let extensions_names = [
-            ash::khr::swapchain::NAME.as_ptr(),
-            #[cfg(any(target_os = "macos", target_os = "ios"))]
-            ash::khr::portability_subset::NAME.as_ptr(),
-        ];
+        let mut extensions_names = Vec::with_capacity(2);
+        if surface.is_some() {
+            extensions_names.push(ash::khr::swapchain::NAME.as_ptr());
+        }
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        extensions_names.push(ash::khr::portability_subset::NAME.as_ptr());
 
         let device_create_info = vk::DeviceCreateInfo::default()
             .queue_create_infos(std::slice::from_ref(&queue_info))
             .enabled_extension_names(&extensions_names)
-            .enabled_features(&features);
+            .enabled_features(&features)
+            .push_next(&mut features_vulkan_12);
 
         let vk_device = unsafe {
             vk_instance
@@ -1224,11 +1444,23 @@ impl Display {
                 vk_debug,
                 memory_properties: device_memory_properties,
                 queue_family_index,
-                _vk_entry: vk_entry,
+                vk_entry,
             }),
         };
 
-        let swapchain = unsafe { Swapchain::create(&gpu, &surface) };
+        let target = match window.zip(surface) {
+            Some((window, surface)) => {
+                let swapchain = unsafe { Swapchain::create(&gpu, &surface) };
+                Target::Window {
+                    window,
+                    surface,
+                    swapchain: Arc::new(swapchain),
+                }
+            }
+            None => Target::Offscreen(unsafe {
+                Offscreen::create(&gpu, desc.resolution, OFFSCREEN_IMAGES_COUNT)
+            }),
+        };
 
         let present_complete_sempahore_create_info = vk::SemaphoreCreateInfo::default();
         let present_complete_semaphore = unsafe {
@@ -1237,15 +1469,17 @@ impl Display {
         };
 
         let (depth_image, depth_image_view, depth_image_memory) =
-            unsafe { Self::create_depth_image(&gpu, surface.vk_surface_resolution) };
+            unsafe { Self::create_depth_image(&gpu, target.vk_resolution()) };
 
         Self {
             gpu,
-            surface,
-            window,
-            swapchain: Arc::new(swapchain),
+            target,
             present_complete_semaphore,
             render_complete_semaphore: vk::Semaphore::null(),
+            frame_requests: Mutex::new(Vec::new()),
+            surface_outdated: Arc::new(AtomicBool::new(false)),
+            surface_lost: Arc::new(AtomicBool::new(false)),
+            requested_resolution: None,
             depth_image,
             depth_image_view,
             depth_image_memory,
@@ -1329,7 +1563,7 @@ impl Display {
             self.depth_image_memory,
         );
         let (depth_image, depth_image_view, depth_image_memory) =
-            Self::create_depth_image(&self.gpu, self.surface.vk_surface_resolution);
+            Self::create_depth_image(&self.gpu, self.target.vk_resolution());
         self.depth_image = depth_image;
         self.depth_image_view = depth_image_view;
         self.depth_image_memory = depth_image_memory;
@@ -1338,8 +1572,15 @@ impl Display {
     /// # Safety
     ///
     /// This function requires valid Vulkan entities
-    pub unsafe fn swapchain_image_views(&self) -> std::slice::Iter<vk::ImageView> {
-        self.swapchain.vk_present_image_views.iter()
+    pub unsafe fn swapchain_image_views(&self) -> Vec<vk::ImageView> {
+        match &self.target {
+            Target::Window { swapchain, .. } => swapchain.vk_present_image_views.clone(),
+            Target::Offscreen(offscreen) => offscreen
+                .images
+                .iter()
+                .map(|image| image.image_view)
+                .collect(),
+        }
     }
 
     /// # Safety
@@ -1360,50 +1601,94 @@ impl Display {
         self.gpu.clone()
     }
 
-    pub fn next_frame(&self) -> u32 {
-        let (present_index, is_suboptimal) = unsafe {
-            log::debug!("Begin acquire image");
-            self.swapchain
-                .loader
-                .acquire_next_image(
-                    self.swapchain.vk_swapchain,
-                    u64::MAX,
-                    self.present_complete_semaphore,
-                    vk::Fence::null(),
-                )
-                .unwrap()
+    /// Acquires the next image to render into
+    ///
+    /// Returns `None` if there is no image available, e.g. the window is minimized, the
+    /// swapchain is out of date or the surface is lost. In the last cases the surface will be
+    /// rebuilt on the next frame.
+    pub fn next_frame(&self) -> Option<u32> {
+        let swapchain = match &self.target {
+            Target::Window { swapchain, .. } => swapchain,
+            Target::Offscreen(offscreen) => {
+                let images_count = offscreen.images.len() as u32;
+                let present_index =
+                    offscreen.next_index.fetch_add(1, Ordering::Relaxed) % images_count;
+                // NOTE: there is nothing to acquire, so just signal the semaphore for the renderer
+                let signal_semaphores = [self.present_complete_semaphore];
+                let submits = [vk::SubmitInfo::default().signal_semaphores(&signal_semaphores)];
+                unsafe {
+                    self.gpu
+                        .submit_queue(&submits, vk::Fence::null())
+                        .expect("Failed to signal offscreen image acquisition");
+                }
+                log::debug!("Offscreen image: {}", present_index);
+                return Some(present_index);
+            }
         };
-        log::debug!(
-            "End acquire image: {}, is_suboptimal: {}",
-            present_index,
-            is_suboptimal
-        );
-        present_index
+
+        let surface_resolution = self.surface_resolution();
+        if surface_resolution.width == 0 || surface_resolution.height == 0 {
+            log::debug!("Surface has zero size, skip acquire");
+            return None;
+        }
+
+        log::debug!("Begin acquire image");
+        let result = unsafe {
+            swapchain.loader.acquire_next_image(
+                swapchain.vk_swapchain,
+                u64::MAX,
+                self.present_complete_semaphore,
+                vk::Fence::null(),
+            )
+        };
+        log::debug!("End acquire image: {:?}", result);
+        acquired_index(result, &self.surface_outdated, &self.surface_lost)
     }
 
-    // pub fn surface(&self) -> &Surface {
-    //    &self.surface
-    // }
+    /// Returns true if the display renders into offscreen images instead of a window
+    pub fn is_headless(&self) -> bool {
+        matches!(self.target, Target::Offscreen(_))
+    }
 
     pub fn surface_format(&self) -> vk::Format {
-        self.surface.vk_surface_format.format
+        self.target.format()
     }
 
     pub fn surface_resolution(&self) -> Extent2D {
-        self.surface.resolution()
+        self.target.resolution()
+    }
+
+    /// Returns layout, that surface images must have at the end of rendering
+    pub fn surface_final_layout(&self) -> vk::ImageLayout {
+        match &self.target {
+            Target::Window { .. } => vk::ImageLayout::PRESENT_SRC_KHR,
+            Target::Offscreen(_) => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        }
+    }
+
+    /// Requests the surface to be resized to match new size of the window
+    pub fn request_resize(&mut self, resolution: Extent2D) {
+        self.requested_resolution = Some(resolution);
     }
 
+    /// Returns true if the surface has to be rebuilt
     pub fn surface_resize_request(&self) -> bool {
         log::debug!("surface_resize_request()");
+        if self.is_headless() {
+            return false;
+        }
         let surface_resolution = self.surface_resolution();
         log::debug!("surface_resolution={:?}", surface_resolution);
-        let window_resolution = self.window.resolution();
-        log::debug!("window_resolution={:?}", window_resolution);
-        surface_resolution != window_resolution
+        log::debug!("requested_resolution={:?}", self.requested_resolution);
+        resize_required(
+            self.surface_outdated.load(Ordering::Relaxed),
+            surface_resolution,
+            self.requested_resolution,
+        )
     }
 
     pub fn surface_changed(&self, surface_version: u64) -> Option<u64> {
-        let current_surface_version = self.surface.version;
+        let current_surface_version = self.surface_version();
         if current_surface_version != surface_version {
             Some(current_surface_version)
         } else {
@@ -1412,19 +1697,48 @@ impl Display {
     }
 
     pub fn surface_version(&self) -> u64 {
-        self.surface.version
+        self.target.version()
     }
 
+    /// Rebuilds the swapchain and the depth image using current size of the surface
     pub fn resize_surface(&mut self) {
         log::debug!("Resize surface");
-        let window_resolution = self.window.resolution();
-        let surface_resolution = match self.surface.vk_surface_capabilities.current_extent.width {
-            u32::MAX => vk::Extent2D {
-                width: window_resolution.width,
-                height: window_resolution.height,
-            },
-            _ => self.surface.vk_surface_capabilities.current_extent,
+        let (window, surface, swapchain) = match &mut self.target {
+            Target::Window {
+                window,
+                surface,
+                swapchain,
+            } => (window, surface, swapchain),
+            Target::Offscreen(_) => return,
         };
+        if self.surface_lost.load(Ordering::Relaxed) {
+            let window_resolution = window.resolution();
+            if window_resolution.width == 0 || window_resolution.height == 0 {
+                // NOTE: surface will be recreated when the window is restored
+                return;
+            }
+            unsafe {
+                self.gpu
+                    .device_wait_idle()
+                    .expect("Failed to wait for device");
+                swapchain.destroy(&self.gpu);
+                surface.recreate(
+                    window,
+                    &self.gpu.device.vk_instance,
+                    &self.gpu.device.vk_entry,
+                );
+                *swapchain = Arc::new(Swapchain::create(&self.gpu, surface));
+            }
+            self.surface_lost.store(false, Ordering::Relaxed);
+        }
+        unsafe {
+            surface.update_capabilities();
+        }
+        let capabilities = &surface.vk_surface_capabilities;
+        let window_resolution = self
+            .requested_resolution
+            .unwrap_or_else(|| window.resolution());
+        let surface_resolution = swapchain_extent(capabilities, window_resolution);
 
         log::debug!(
             "swapchain_size: {:?}, window size: {:?}",
@@ -1432,26 +1746,39 @@ impl Display {
             window_resolution
         );
 
-        self.surface.vk_surface_resolution = surface_resolution;
+        surface.vk_surface_resolution = surface_resolution;
+        if surface_resolution.width == 0 || surface_resolution.height == 0 {
+            // NOTE: window is minimized, swapchain will be rebuilt when it is restored
+            return;
+        }
+
+        unsafe {
+            self.gpu
+                .device_wait_idle()
+                .expect("Failed to wait for device");
+            swapchain.destroy(&self.gpu);
+            *swapchain = Arc::new(Swapchain::create(&self.gpu, surface));
+        }
+        surface.version += 1;
+        self.surface_outdated.store(false, Ordering::Relaxed);
+
         unsafe {
-            // self.destroy_framebuffers();
-            self.swapchain.destroy(&self.gpu);
-            self.swapchain = Arc::new(Swapchain::create(&self.gpu, &self.surface));
-            // self.framebuffers = Vulkan::create_framebuffers(
-            //    &self.vk_device,
-            //    &self.surface,
-            //    &self.swapchain,
-            //    &self.renderpass,
-            //);
+            self.recreate_depth_image();
         }
-        self.surface.version += 1;
     }
 
-    pub fn presenter(&self, swapchain_index: u32) -> FramePresenter {
+    /// Returns presenter of the frame, `None` index means that the frame was skipped
+    pub fn presenter(&self, swapchain_index: Option<u32>) -> FramePresenter {
+        let swapchain = match &self.target {
+            Target::Window { swapchain, .. } => Some(Arc::clone(swapchain)),
+            Target::Offscreen(_) => None,
+        };
         FramePresenter {
-            swapchain: Arc::clone(&self.swapchain),
+            swapchain,
             device: Arc::clone(&self.gpu.device),
             render_complete_semaphore: self.render_complete_semaphore,
+            surface_outdated: Arc::clone(&self.surface_outdated),
+            surface_lost: Arc::clone(&self.surface_lost),
             swapchain_index,
         }
     }
@@ -1467,21 +1794,210 @@ impl Display {
         self.present_complete_semaphore
     }
 
+    /// Reads rendered frame back from GPU
+    ///
+    /// Must be called after rendering of the frame, but before its presentation. Returns `None`
+    /// if the index is out of range or the surface does not allow to read its images.
+    pub fn read_frame(&self, swapchain_index: u32) -> Option<Image> {
+        let (image, layout) = match &self.target {
+            Target::Window {
+                surface, swapchain, ..
+            } => {
+                if !surface
+                    .vk_surface_capabilities
+                    .supported_usage_flags
+                    .contains(vk::ImageUsageFlags::TRANSFER_SRC)
+                {
+                    log::warn!("Surface images can not be read");
+                    return None;
+                }
+                let image = swapchain.vk_present_images.get(swapchain_index as usize)?;
+                (*image, vk::ImageLayout::PRESENT_SRC_KHR)
+            }
+            Target::Offscreen(offscreen) => {
+                let image = offscreen.images.get(swapchain_index as usize)?;
+                (image.image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            }
+        };
+
+        let swizzle = match self.surface_format() {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+            format => {
+                log::warn!("Surface format {:?} can not be read", format);
+                return None;
+            }
+        };
+
+        let resolution = self.surface_resolution();
+        let data = unsafe { self.read_image(image, layout, resolution) }
+            .expect("Failed to read frame image");
+        // NOTE: the image is copied into the buffer tightly packed
+        let row_pitch = resolution.width as usize * 4;
+
+        Some(Image::new(
+            format!("frame-{}", swapchain_index),
+            resolution,
+            rgba_pixels(&data, resolution, row_pitch, swizzle),
+        ))
+    }
+
+    /// Requests the next submitted frame to be read back from GPU
+    ///
+    /// The frame is read by [`super::SubmitFrame`] task and sent to the returned receiver
+    pub fn request_frame(&self) -> mpsc::Receiver<Image> {
+        let (tx, rx) = mpsc::channel();
+        self.frame_requests
+            .lock()
+            .expect("Mutex to be locked")
+            .push(tx);
+        rx
+    }
+
+    /// Reads the frame for all pending requests
+    pub fn serve_frame_requests(&self, swapchain_index: u32) {
+        let requests =
+            std::mem::take(&mut *self.frame_requests.lock().expect("Mutex to be locked"));
+        if requests.is_empty() {
+            return;
+        }
+        if let Some(image) = self.read_frame(swapchain_index) {
+            for request in requests {
+                request.send(image.clone()).ok();
+            }
+        }
+    }
+
+    /// Copies color image of 4 bytes per pixel into host memory
+    ///
     /// # Safety
     ///
     /// This function requires valid Vulkan entities
-    unsafe fn create_instance(desc: &DisplaySetup, vk_entry: &ash::Entry) -> ash::Instance {
-        let window = &desc.window_instance;
+    unsafe fn read_image(
+        &self,
+        image: vk::Image,
+        layout: vk::ImageLayout,
+        resolution: Extent2D,
+    ) -> Result<Vec<u8>, vk::Result> {
+        let gpu = &self.gpu;
+        gpu.device_wait_idle()?;
+
+        let size = resolution.width as u64 * resolution.height as u64 * 4;
+        let staging_buffer_create_info = vk::BufferCreateInfo {
+            size,
+            usage: vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+        let staging_buffer = Buffer::create_and_allocate(gpu, &staging_buffer_create_info)?;
+
+        let pool_create_info = vk::CommandPoolCreateInfo::default()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(gpu.queue_family_index());
+        let command_pool = gpu.create_command_pool(&pool_create_info)?;
+        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
+            .command_buffer_count(1)
+            .command_pool(command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY);
+        let command_buffer: vk::CommandBuffer = gpu
+            .allocate_command_buffers(&command_buffer_allocate_info)
+            .into();
+
+        let command_buffer_begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        gpu.begin_command_buffer(command_buffer, &command_buffer_begin_info)?;
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1)
+            .level_count(1);
+
+        let transfer_barrier = vk::ImageMemoryBarrier::default()
+            .image(image)
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .old_layout(layout)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .subresource_range(subresource_range);
+
+        gpu.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[transfer_barrier],
+        );
 
-        //let (surface_width, surface_height) = desc.window.size();
-        let raw_display_handle = window
-            .display_handle()
-            .expect("Could not get display handle")
-            .as_raw();
-        //let raw_window_handle = desc.window.handle().raw_window_handle();
-        let mut extensions = ash_window::enumerate_required_extensions(raw_display_handle)
-            .expect("Failed to obtain extensions requirements")
-            .to_vec();
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .layer_count(1),
+            )
+            .image_extent(vk::Extent3D {
+                width: resolution.width,
+                height: resolution.height,
+                depth: 1,
+            });
+
+        gpu.cmd_copy_image_to_buffer(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            staging_buffer.handle,
+            &[region],
+        );
+
+        let restore_barrier = vk::ImageMemoryBarrier::default()
+            .image(image)
+            .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .new_layout(layout)
+            .subresource_range(subresource_range);
+
+        gpu.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[restore_barrier],
+        );
+
+        gpu.end_command_buffer(command_buffer)?;
+
+        let command_buffers = [command_buffer];
+        let submits = [vk::SubmitInfo::default().command_buffers(&command_buffers)];
+        gpu.submit_queue(&submits, vk::Fence::null())?;
+        gpu.queue_wait_idle()?;
+
+        let data = staging_buffer.map_and_read_from_device_memory(gpu, 0, size);
+
+        gpu.destroy_command_pool(command_pool);
+        staging_buffer.free_memory_and_destroy(gpu);
+
+        Ok(data)
+    }
+
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    unsafe fn create_instance(desc: &DisplaySetup, vk_entry: &ash::Entry) -> ash::Instance {
+        let mut extensions = match desc.window_instance.as_ref() {
+            Some(window) => {
+                let raw_display_handle = window
+                    .display_handle()
+                    .expect("Could not get display handle")
+                    .as_raw();
+                ash_window::enumerate_required_extensions(raw_display_handle)
+                    .expect("Failed to obtain extensions requirements")
+                    .to_vec()
+            }
+            None => Vec::new(),
+        };
 
         if desc.debug {
             extensions.push(ash::ext::debug_utils::NAME.as_ptr());
@@ -1506,7 +2022,7 @@ impl Display {
             .application_version(desc.app_version)
             .engine_name(&engine_name)
             .engine_version(engine_version)
-            .api_version(vk::make_api_version(0, 1, 0, 0));
+            .api_version(vk::make_api_version(0, 1, 2, 0));
 
         let create_flags = if cfg!(any(target_os = "macos", target_os = "ios")) {
             vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR
@@ -1514,7 +2030,12 @@ impl Display {
             vk::InstanceCreateFlags::default()
         };
 
-        let layers_names_raw: Vec<*const c_char> = [b"VK_LAYER_KHRONOS_validation\0"]
+        let layers_names: &[&[u8]] = if desc.debug {
+            &[b"VK_LAYER_KHRONOS_validation\0"]
+        } else {
+            &[]
+        };
+        let layers_names_raw: Vec<*const c_char> = layers_names
             .iter()
             .map(|&raw_name| unsafe { CStr::from_bytes_with_nul_unchecked(raw_name).as_ptr() })
             .collect();
@@ -1536,7 +2057,7 @@ impl Display {
     /// Returns device instance that sattisfies requirements
     unsafe fn select_device(
         vk_instance: &ash::Instance,
-        surface: &Surface,
+        surface: Option<&Surface>,
         device_type_request: Option<&DeviceType>,
     ) -> (vk::PhysicalDevice, u32) {
         let vk_devices = vk_instance
@@ -1566,6 +2087,21 @@ impl Display {
                         }
                     }
 
+                    let device_name = CStr::from_ptr(device_properties.device_name.as_ptr());
+
+                    if !Self::supports_required_features(
+                        vk_instance,
+                        *p_device,
+                        device_properties.api_version,
+                    ) {
+                        log::warn!(
+                            "Skip GPU: {:?} ({:?}), required features are not supported",
+                            device_name,
+                            device_type
+                        );
+                        return None;
+                    }
+
                     vk_instance
                         .get_physical_device_queue_family_properties(*p_device)
                         .iter()
@@ -1577,13 +2113,12 @@ impl Display {
                             if !info.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
                                 return None;
                             }
-                            if !surface.get_physical_device_support(*p_device, index as u32) {
-                                return None;
+                            if let Some(surface) = surface {
+                                if !surface.get_physical_device_support(*p_device, index as u32) {
+                                    return None;
+                                }
                             }
 
-                            let device_name =
-                                CStr::from_ptr(device_properties.device_name.as_ptr());
-
                             log::info!("Select GPU: {:?} ({:?})", device_name, device_type);
                             Some((*p_device, index as u32))
                         })
@@ -1592,6 +2127,32 @@ impl Display {
             .expect("Could not find a device that fulfill requirements")
     }
 
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    /// Returns true if the device supports features, enabled by [`Display::new`]
+    unsafe fn supports_required_features(
+        vk_instance: &ash::Instance,
+        p_device: vk::PhysicalDevice,
+        api_version: u32,
+    ) -> bool {
+        // Vulkan 1.2 features can not be queried from older devices
+        if api_version < vk::API_VERSION_1_2 {
+            return false;
+        }
+        let mut features_vulkan_12 = vk::PhysicalDeviceVulkan12Features::default();
+        let features = {
+            let mut features =
+                vk::PhysicalDeviceFeatures2::default().push_next(&mut features_vulkan_12);
+            vk_instance.get_physical_device_features2(p_device, &mut features);
+            features.features
+        };
+        features.shader_clip_distance == vk::TRUE
+            && features.vertex_pipeline_stores_and_atomics == vk::TRUE
+            && features_vulkan_12.runtime_descriptor_array == vk::TRUE
+            && features_vulkan_12.shader_sampled_image_array_non_uniform_indexing == vk::TRUE
+    }
+
     /*
     pub fn present(&self) {
         let present_index = self.next_frame();
@@ -1755,31 +2316,82 @@ impl<'a> From<&'a Display> for &'a Device {
 }
 
 pub struct FramePresenter {
-    swapchain: Arc<Swapchain>,
+    swapchain: Option<Arc<Swapchain>>,
     device: Arc<Device>,
     render_complete_semaphore: vk::Semaphore,
-    swapchain_index: u32,
+    surface_outdated: Arc<AtomicBool>,
+    surface_lost: Arc<AtomicBool>,
+    swapchain_index: Option<u32>,
 }
 
 impl FramePresenter {
+    /// Returns swapchain index of the frame, `None` if the frame was skipped
+    pub fn swapchain_index(&self) -> Option<u32> {
+        self.swapchain_index
+    }
+
     pub fn present(self) {
+        let swapchain_index = match self.swapchain_index {
+            Some(swapchain_index) => swapchain_index,
+            None => {
+                log::debug!("Frame was skipped, nothing to present");
+                return;
+            }
+        };
+        let swapchain = match self.swapchain.as_ref() {
+            Some(swapchain) => swapchain,
+            None => return self.complete_offscreen(swapchain_index),
+        };
         let wait_semaphores = [self.render_complete_semaphore];
-        let swapchains = [self.swapchain.as_ref().vk_swapchain];
-        let image_indices = [self.swapchain_index];
+        let swapchains = [swapchain.vk_swapchain];
+        let image_indices = [swapchain_index];
         let present_info = vk::PresentInfoKHR::default()
             .wait_semaphores(&wait_semaphores)
             .swapchains(&swapchains)
             .image_indices(&image_indices);
 
-        log::debug!("Begin present: {}", self.swapchain_index);
+        log::debug!("Begin present: {}", swapchain_index);
 
-        unsafe {
-            let r = self
-                .swapchain
+        let result = unsafe {
+            swapchain
                 .loader
-                .queue_present(self.device.vk_queue, &present_info);
-            log::debug!("end present: {:?}", r);
-            r.unwrap();
+                .queue_present(self.device.vk_queue, &present_info)
+        };
+        log::debug!("end present: {:?}", result);
+        match result {
+            Ok(false) => {}
+            Ok(true) => {
+                self.surface_outdated.store(true, Ordering::Relaxed);
+            }
+            Err(err) => {
+                if !surface_error(err, &self.surface_outdated, &self.surface_lost) {
+                    panic!("Failed to present swapchain image: {:?}", err);
+                }
+            }
+        }
+    }
+
+    /// Waits for the offscreen image to be rendered
+    fn complete_offscreen(&self, swapchain_index: u32) {
+        log::debug!("Complete offscreen image: {}", swapchain_index);
+        let wait_semaphores = [self.render_complete_semaphore];
+        let wait_dst_stage_mask = [vk::PipelineStageFlags::ALL_COMMANDS];
+        let submit_info = if self.render_complete_semaphore != vk::Semaphore::null() {
+            vk::SubmitInfo::default()
+                .wait_semaphores(&wait_semaphores)
+                .wait_dst_stage_mask(&wait_dst_stage_mask)
+        } else {
+            vk::SubmitInfo::default()
+        };
+        unsafe {
+            self.device
+                .vk_device
+                .queue_submit(self.device.vk_queue, &[submit_info], vk::Fence::null())
+                .expect("Failed to complete offscreen image");
+            self.device
+                .vk_device
+                .queue_wait_idle(self.device.vk_queue)
+                .expect("Failed to wait for the queue");
         }
     }
 }
@@ -1920,7 +2532,7 @@ impl Buffer {
         data: &[T],
     ) -> u64 {
         let align = std::mem::align_of::<T>() as u64;
-        let size = (data.len() * std::mem::size_of_val(data)) as u64;
+        let size = std::mem::size_of_val(data) as u64;
 
         let memory_ptr = gpu
             .map_memory(
@@ -1940,13 +2552,115 @@ impl Buffer {
     /// # Safety
     ///
     /// This function requires valid Vulkan entities
+    pub unsafe fn map_and_read_from_device_memory(
+        &self,
+        gpu: &Gpu,
+        offset: u64,
+        size: u64,
+    ) -> Vec<u8> {
+        let memory_ptr = gpu
+            .map_memory(
+                self.device_memory,
+                offset,
+                size,
+                vk::MemoryMapFlags::empty(),
+            )
+            .expect("Could not map buffer memory");
+
+        let data = std::slice::from_raw_parts(memory_ptr as *const u8, size as usize).to_vec();
+        gpu.unmap_memory(self.device_memory);
+        data
+    }
+
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities, the buffer must not be in use by the
+    /// device, e.g. the owner waits for the device to be idle
     pub unsafe fn free_memory_and_destroy(&self, gpu: &Gpu) {
-        gpu.device_wait_idle().expect("Device is not idle");
         gpu.free_memory(self.device_memory);
         gpu.destroy_buffer(self.handle);
     }
 }
 
+pub struct Texture {
+    pub image: vk::Image,
+    pub image_view: vk::ImageView,
+    pub device_memory: vk::DeviceMemory,
+    pub format: vk::Format,
+    pub resolution: Extent2D,
+}
+
+impl Texture {
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities
+    pub unsafe fn create_and_allocate(
+        gpu: &Gpu,
+        resolution: Extent2D,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+    ) -> Result<Texture, vk::Result> {
+        let image_create_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width: resolution.width,
+                height: resolution.height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let image = gpu.create_image(&image_create_info)?;
+        let image_memory_req = gpu.get_image_memory_requirements(image);
+        let image_memory_index = gpu
+            .find_memory_type_index(&image_memory_req, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+            .expect("Unable to find suitable memory index for the texture.");
+
+        let image_allocate_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(image_memory_req.size)
+            .memory_type_index(image_memory_index);
+
+        let device_memory = gpu.allocate_memory(&image_allocate_info)?;
+        gpu.bind_image_memory(image, device_memory, 0)?;
+
+        let image_view_info = vk::ImageViewCreateInfo::default()
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .level_count(1)
+                    .layer_count(1),
+            )
+            .image(image)
+            .format(format)
+            .view_type(vk::ImageViewType::TYPE_2D);
+
+        let image_view = gpu.create_image_view(&image_view_info)?;
+
+        Ok(Texture {
+            image,
+            image_view,
+            device_memory,
+            format,
+            resolution,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// This function requires valid Vulkan entities, the texture must not be in use by the
+    /// device, e.g. the owner waits for the device to be idle
+    pub unsafe fn free_memory_and_destroy(&self, gpu: &Gpu) {
+        gpu.destroy_image_view(self.image_view);
+        gpu.destroy_image(self.image);
+        gpu.free_memory(self.device_memory);
+    }
+}
+
 /*
 
 pub struct PipelineLayout {
@@ -2068,3 +2782,233 @@ unsafe extern "system" fn vulkan_debug_callback(
 
     vk::FALSE
 }
+
+/// Returns index of the acquired image, marks the surface outdated if it has to be rebuilt
+fn acquired_index(
+    result: Result<(u32, bool), vk::Result>,
+    surface_outdated: &AtomicBool,
+    surface_lost: &AtomicBool,
+) -> Option<u32> {
+    match result {
+        Ok((present_index, is_suboptimal)) => {
+            if is_suboptimal {
+                surface_outdated.store(true, Ordering::Relaxed);
+            }
+            Some(present_index)
+        }
+        Err(err) if surface_error(err, surface_outdated, surface_lost) => None,
+        Err(err) => panic!("Failed to acquire swapchain image: {:?}", err),
+    }
+}
+
+/// Marks the surface to be rebuilt, if the error is caused by its state
+///
+/// Returns false if the error is not related to the surface.
+fn surface_error(
+    error: vk::Result,
+    surface_outdated: &AtomicBool,
+    surface_lost: &AtomicBool,
+) -> bool {
+    match error {
+        vk::Result::ERROR_OUT_OF_DATE_KHR => {}
+        vk::Result::ERROR_SURFACE_LOST_KHR => {
+            log::warn!("Surface is lost");
+            surface_lost.store(true, Ordering::Relaxed);
+        }
+        _ => return false,
+    }
+    surface_outdated.store(true, Ordering::Relaxed);
+    true
+}
+
+/// Returns true if the window surface has to be rebuilt
+fn resize_required(
+    surface_outdated: bool,
+    surface_resolution: Extent2D,
+    requested_resolution: Option<Extent2D>,
+) -> bool {
+    surface_outdated
+        || requested_resolution
+            .map(|resolution| resolution != surface_resolution)
+            .unwrap_or(false)
+}
+
+/// Returns size of the swapchain images for the window of the given size
+fn swapchain_extent(
+    capabilities: &vk::SurfaceCapabilitiesKHR,
+    window_resolution: Extent2D,
+) -> vk::Extent2D {
+    match capabilities.current_extent.width {
+        u32::MAX => vk::Extent2D {
+            width: window_resolution.width.clamp(
+                capabilities.min_image_extent.width,
+                capabilities.max_image_extent.width,
+            ),
+            height: window_resolution.height.clamp(
+                capabilities.min_image_extent.height,
+                capabilities.max_image_extent.height,
+            ),
+        },
+        _ => capabilities.current_extent,
+    }
+}
+
+/// Converts pixels of the read back image into tightly packed RGBA
+///
+/// Rows of `data` start every `row_pitch` bytes, `bgra` tells that red and blue channels are
+/// swapped.
+fn rgba_pixels(data: &[u8], resolution: Extent2D, row_pitch: usize, bgra: bool) -> Vec<u8> {
+    let row_size = resolution.width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_size * resolution.height as usize);
+    for row in data.chunks(row_pitch).take(resolution.height as usize) {
+        pixels.extend_from_slice(&row[0..row_size]);
+    }
+    if bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use ash::vk;
+
+    use super::{acquired_index, resize_required, rgba_pixels, swapchain_extent, Display};
+    use crate::graphics::{DisplaySetup, Extent2D};
+
+    #[test]
+    fn outdated_surface_is_resized() {
+        let outdated = AtomicBool::new(false);
+        let lost = AtomicBool::new(false);
+        assert_eq!(acquired_index(Ok((1, false)), &outdated, &lost), Some(1));
+        assert!(!outdated.load(Ordering::Relaxed));
+        // suboptimal image is still rendered, but the surface gets rebuilt
+        assert_eq!(acquired_index(Ok((2, true)), &outdated, &lost), Some(2));
+        assert!(outdated.load(Ordering::Relaxed));
+        let outdated = AtomicBool::new(false);
+        assert_eq!(
+            acquired_index(Err(vk::Result::ERROR_OUT_OF_DATE_KHR), &outdated, &lost),
+            None
+        );
+        assert!(outdated.load(Ordering::Relaxed));
+        assert!(!lost.load(Ordering::Relaxed));
+        // lost surface is recreated together with the swapchain
+        let outdated = AtomicBool::new(false);
+        assert_eq!(
+            acquired_index(Err(vk::Result::ERROR_SURFACE_LOST_KHR), &outdated, &lost),
+            None
+        );
+        assert!(outdated.load(Ordering::Relaxed));
+        assert!(lost.load(Ordering::Relaxed));
+
+        let resolution = Extent2D {
+            width: 800,
+            height: 600,
+        };
+        let requested = Extent2D {
+            width: 1024,
+            height: 768,
+        };
+        assert!(resize_required(true, resolution, None));
+        assert!(resize_required(false, resolution, Some(requested)));
+        assert!(!resize_required(false, resolution, Some(resolution)));
+        assert!(!resize_required(false, resolution, None));
+
+        let capabilities = vk::SurfaceCapabilitiesKHR {
+            current_extent: vk::Extent2D {
+                width: u32::MAX,
+                height: u32::MAX,
+            },
+            min_image_extent: vk::Extent2D {
+                width: 1,
+                height: 1,
+            },
+            max_image_extent: vk::Extent2D {
+                width: 1000,
+                height: 1000,
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            swapchain_extent(&capabilities, requested),
+            vk::Extent2D {
+                width: 1000,
+                height: 768
+            }
+        );
+        // minimized window
+        let capabilities = vk::SurfaceCapabilitiesKHR {
+            current_extent: vk::Extent2D {
+                width: 0,
+                height: 0,
+            },
+            ..capabilities
+        };
+        assert_eq!(
+            swapchain_extent(&capabilities, requested),
+            vk::Extent2D {
+                width: 0,
+                height: 0
+            }
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a Vulkan device"]
+    fn offscreen_frames_are_acquired() {
+        let resolution = Extent2D {
+            width: 64,
+            height: 48,
+        };
+        let mut display = Display::new(DisplaySetup {
+            window_instance: None,
+            resolution,
+            app_name: "test",
+            app_version: 0,
+            debug: false,
+            device_type_request: None,
+        });
+        let indices = (0..4)
+            .map(|_| display.next_frame())
+            .collect::<Option<Vec<_>>>()
+            .expect("Offscreen images should be always available");
+        assert_eq!(indices[0], indices[2]);
+        assert_ne!(indices[0], indices[1]);
+
+        // offscreen images are not resized
+        display.request_resize(Extent2D {
+            width: 32,
+            height: 32,
+        });
+        assert!(!display.surface_resize_request());
+        assert_eq!(display.surface_resolution(), resolution);
+    }
+
+    #[test]
+    fn read_back_pixels_are_converted_to_rgba() {
+        let resolution = Extent2D {
+            width: 2,
+            height: 2,
+        };
+        // BGRA rows, padded to 12 bytes
+        let data = [
+            [3, 2, 1, 255, 6, 5, 4, 255, 0, 0, 0, 0],
+            [9, 8, 7, 255, 12, 11, 10, 255, 0, 0, 0, 0],
+        ]
+        .concat();
+
+        let rgba = [
+            [1, 2, 3, 255, 4, 5, 6, 255],
+            [7, 8, 9, 255, 10, 11, 12, 255],
+        ]
+        .concat();
+        assert_eq!(rgba_pixels(&data, resolution, 12, true), rgba);
+
+        let packed = rgba_pixels(&data, resolution, 12, false);
+        assert_eq!(rgba_pixels(&packed, resolution, 8, true), rgba);
+    }
+}
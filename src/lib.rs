@@ -29,7 +29,8 @@ pub mod math;
 /// Models abstractions
 pub mod models;
 pub use models::{
-    Animation, Armature, Color, Image, Joint, Material, Mesh, Model, RenderModels, Transform,
+    Animate, Animation, AnimationPlayer, Armature, Children, Color, GlobalTransform, Image, Joint,
+    Material, Mesh, Model, Parent, Pose, PropagateTransforms, RenderModels, Scene, Transform,
     VertexAttribute, VertexBitangent, VertexJoints, VertexNormal, VertexPosition, VertexTangent,
     VertexTexture, VertexWeights,
 };
@@ -48,11 +49,11 @@ pub use utils::Id;
 
 /// World
 pub mod world;
-pub use world::{Entity, World};
+pub use world::{Camera, Entity, Light, World};
 
 /// Window API and input events
 pub mod window;
-pub use window::{ReadInput, Window};
+pub use window::{Actions, Bindings, Input, InputSource, MapActions, ReadInput, Window};
 
 //pub use utils::{ Id };
 
@@ -139,9 +140,140 @@ pub trait Application {
             height: 600,
         }
     }
+
+    /// Source of the input events
+    fn input_source(&self) -> window::InputSource {
+        window::InputSource::Live
+    }
+
+    /// Fixed duration of frames, reported regardless of the real time, e.g. while recording input
+    fn fixed_timestep(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Dead zone of gamepad sticks, gamepads are enabled only if it is set, e.g. to
+    /// [`window::DEFAULT_DEAD_ZONE`]
+    fn gamepad_dead_zone(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// Adds built-in tasks and the display to the task manager and starts up the application
+///
+/// Shared by [`run`] and [`run_headless`], so both run the same frame and input pipeline.
+pub(crate) fn setup<A: Application>(
+    application: A,
+    task_manager: &TaskManager,
+    mut display: Display,
+) {
+    // resize requests and input events are provided by the launcher
+    task_manager.register::<window::ResizeRequest>(0);
+    task_manager.register::<window::Event>(0);
+
+    let scheduler = task_manager.scheduler();
+
+    let create_frame_task = graphics::CreateFrame::default()
+        .log_fps_interval(application.log_fps_interval())
+        .fps_request(application.fps_request())
+        .fixed_timestep(application.fixed_timestep());
+
+    let submit_frame_task = graphics::SubmitFrame::default();
+    scheduler.add_task(submit_frame_task);
+
+    window::add_input_tasks(&scheduler, application.input_source(), create_frame_task);
+
+    // applies commands of tasks, that can not mutate the world directly
+    scheduler.add_task(world::ApplyCommands::default());
+
+    application.startup(&scheduler, &mut display);
+
+    // add Display context
+    scheduler.add_context(display);
 }
 
 /// Application launcher
 pub fn run<A: Application>(application: A) {
     window::EventLoop::new(application).run();
 }
+
+/// Headless application launcher
+///
+/// Renders `frames` frames into offscreen images without a window and returns the last one.
+/// Software Vulkan implementations like lavapipe are reported as CPU devices, so the application
+/// should return `None` from [`Application::device_type_request`] to be able to use them.
+pub fn run_headless<A: Application>(application: A, frames: u64) -> Option<Image> {
+    let task_manager = TaskManager::new::<graphics::FramePresenter>(application.workers());
+
+    let display_setup = graphics::DisplaySetup {
+        window_instance: None,
+        resolution: application.resolution(),
+        app_name: application.app_name(),
+        app_version: application.app_version(),
+        debug: application.debug(),
+        device_type_request: application.device_type_request(),
+    };
+
+    let display = Display::new(display_setup);
+    setup(application, &task_manager, display);
+
+    let mut swapchain_index = None;
+    for _ in 0..frames {
+        task_manager.run();
+        let presenter = task_manager.wait_for::<graphics::FramePresenter>();
+        swapchain_index = presenter.swapchain_index().or(swapchain_index);
+        presenter.present();
+    }
+
+    let display = task_manager.remove_global_context::<Display>()?;
+    display.read_frame(swapchain_index?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::{Display, Extent2D};
+    use crate::tasks::Scheduler;
+    use crate::{Application, Assets, Camera, DeviceType, RenderModels, World};
+
+    struct Headless;
+
+    impl Application for Headless {
+        fn startup(self, scheduler: &Scheduler, display: &mut Display) {
+            let renderer = RenderModels::setup()
+                .surface_format(display.surface_format())
+                .create(display);
+            scheduler.add_context(Assets::default());
+            scheduler.add_context(World::default());
+            scheduler.add_context(Camera::default());
+            crate::models::add_render_tasks(scheduler, renderer);
+        }
+
+        fn device_type_request(&self) -> Option<DeviceType> {
+            None
+        }
+
+        fn resolution(&self) -> Extent2D {
+            Extent2D {
+                width: 64,
+                height: 48,
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a Vulkan device"]
+    fn headless_frame_is_rendered() {
+        let image = crate::run_headless(Headless, 1).expect("Frame should be rendered");
+        assert_eq!(
+            *image.resolution(),
+            Extent2D {
+                width: 64,
+                height: 48
+            }
+        );
+        assert_eq!(image.data().len(), 64 * 48 * 4);
+        // the clear color is blue, so RGBA pixels have no red component
+        let pixel = &image.data()[0..4];
+        assert_eq!(pixel[0], 0);
+        assert!(pixel[2] > 0);
+    }
+}
@@ -65,6 +65,8 @@ pub trait ResourceLoader: 'static {
 pub struct ResourceBundle {
     pub resource: PathBuf,
     pub bundle: HashMap<ResourceTarget, Option<Box<dyn Asset>>>,
+    /// IDs assigned by the loader to assets referenced by other assets of the bundle
+    pub ids: HashMap<ResourceTarget, (u64, u64)>,
 }
 
 pub struct ResourceReport {
@@ -96,11 +98,21 @@ impl Task for StoreAssets {
     type Output = ResourceReport;
 
     fn run(&mut self, (bundle, mut assets): Self::Context) -> Self::Output {
-        let ResourceBundle { resource, bundle } = bundle.take();
+        let ResourceBundle {
+            resource,
+            bundle,
+            ids,
+        } = bundle.take();
 
         let report = bundle
             .into_iter()
-            .map(|(target, asset)| (target, asset.map(|asset| assets.store(asset))))
+            .map(|(target, asset)| {
+                let id = asset.map(|asset| match ids.get(&target) {
+                    Some(&id) => assets.store_as(id, asset),
+                    None => assets.store(asset),
+                });
+                (target, id)
+            })
             .collect::<HashMap<_, _>>();
 
         ResourceReport { resource, report }
@@ -103,6 +103,19 @@ impl Assets {
         uuid.as_u64_pair()
     }
 
+    /// Stores an already boxed asset under the preassigned id
+    pub fn store_as(&mut self, id: (u64, u64), asset: Box<dyn Asset>) -> (u64, u64) {
+        let uuid = Uuid::from_u64_pair(id.0, id.1);
+        if let Some(previous) = self.registry.insert(String::from(asset.name()), uuid) {
+            if previous != uuid {
+                self.map.remove(&previous);
+            }
+        }
+
+        self.map.insert(uuid, asset);
+        id
+    }
+
     /// Searches for an asset by the name and return [`Id`] of it if the asset exists
     pub fn find<T: Asset>(&self, name: &str) -> Option<Id<T>> {
         self.registry.get(name).map(|uuid| Id::from(*uuid))
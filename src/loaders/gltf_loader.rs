@@ -19,26 +19,61 @@ use gltf::Gltf;
 use crate::log;
 use crate::math::{Mat4, Quat, Vec3};
 use crate::models::{
-    Animation, Armature, Color, Image, ImageFormat, Interpolation, Joint, Material, Mesh,
-    Transform, VertexJoints, VertexNormal, VertexPosition, VertexTexture, VertexWeights,
+    Animation, Armature, Color, Image, ImageFormat, Interpolation, Joint, Material, Mesh, Scene,
+    SceneNode, Transform, VertexJoints, VertexNormal, VertexPosition, VertexTexture, VertexWeights,
 };
 use crate::utils::Id;
 
 use super::{Asset, ImageLoader, ResourceBundle, ResourceLoader, ResourceTarget};
 
 type JsonIndex = usize;
-type ResultIndex = usize;
 
 #[derive(Default)]
 struct Output {
     result: Vec<Box<dyn Asset>>,
-    loaded_images: HashMap<JsonIndex, ResultIndex>,
-    loaded_meshes: HashMap<JsonIndex, ResultIndex>,
-    loaded_materials: HashMap<JsonIndex, ResultIndex>,
-    loaded_armature: HashMap<JsonIndex, ResultIndex>,
+    loaded_images: HashMap<JsonIndex, Id<Image>>,
+    assigned_ids: HashMap<ResourceTarget, (u64, u64)>,
+    /// Meshes by indices of glTF mesh and its primitive
+    loaded_meshes: HashMap<(JsonIndex, JsonIndex), Id<Mesh>>,
+    /// Materials by their indices, `None` for the default material
+    loaded_materials: HashMap<Option<JsonIndex>, Id<Material>>,
+    loaded_armature: HashMap<JsonIndex, Id<Armature>>,
     loaded_joints: HashMap<JsonIndex, Id<Joint>>,
 }
 
+impl Output {
+    /// Adds the asset with a new ID, so other assets can refer to it
+    fn push_with_id<T: Asset>(&mut self, asset: T) -> Id<T> {
+        let id = Id::new();
+        let target = ResourceTarget {
+            type_id: std::any::TypeId::of::<T>(),
+            name: String::from(asset.name()),
+        };
+        debug_assert!(
+            !self.assigned_ids.contains_key(&target),
+            "Asset name `{}` is not unique",
+            asset.name()
+        );
+        self.assigned_ids.insert(target, id.uuid().as_u64_pair());
+        self.result.push(Box::new(asset));
+        id
+    }
+
+    /// Returns the preferred asset name, if no other asset of the type has it yet, or the
+    /// fallback one
+    fn unique_name<T: Asset>(&self, name: String, fallback: impl FnOnce() -> String) -> String {
+        let target = ResourceTarget {
+            type_id: std::any::TypeId::of::<T>(),
+            name,
+        };
+        if self.assigned_ids.contains_key(&target) {
+            fallback()
+        } else {
+            target.name
+        }
+    }
+}
+
 /// Gltf file loader
 #[derive(Default)]
 pub struct GltfLoader;
@@ -66,9 +101,23 @@ impl ResourceLoader for GltfLoader {
                         .expect("Could not read file name to string")
                         .into();
                     for scene in gltf.scenes() {
+                        let scene_name = scene
+                            .name()
+                            .map(|scene_name| [name.as_str(), scene_name].join("::"))
+                            .unwrap_or_else(|| format!("{}::scene[{}]", name, scene.index()));
+                        let mut scene_asset = Scene::new(scene_name);
                         for node in scene.nodes() {
-                            Self::read_node(&mut output, &node, &buffers, &name, None);
+                            Self::read_node(
+                                &mut output,
+                                &mut scene_asset,
+                                &node,
+                                &buffers,
+                                &name,
+                                None,
+                                None,
+                            );
                         }
+                        output.result.push(Box::new(scene_asset));
                     }
                     for animation in gltf.animations() {
                         Self::read_animation(&mut output, &animation, &buffers, &name);
@@ -98,6 +147,7 @@ impl ResourceLoader for GltfLoader {
         ResourceBundle {
             resource: path.into(),
             bundle,
+            ids: output.assigned_ids,
         }
     }
 }
@@ -142,23 +192,51 @@ impl GltfLoader {
         Some(buffers)
     }
 
+    /// Reads the node and its children into the scene
     fn read_node(
         output: &mut Output,
+        scene: &mut Scene,
         node: &gltf::Node,
         buffers: &[Vec<u8>],
         name: &str,
         root: Option<&gltf::Node>,
+        parent: Option<usize>,
     ) {
-        if let Some(skin) = node.skin() {
-            Self::read_armature(output, &skin, buffers, name, root);
-        }
+        let armature = node
+            .skin()
+            .and_then(|skin| Self::read_armature(output, &skin, buffers, name, root))
+            .unwrap_or_default();
+
+        let index = scene.add(SceneNode {
+            name: node.name().map(String::from),
+            parent,
+            transform: Self::read_transform(node),
+            armature,
+            ..Default::default()
+        });
 
         if let Some(mesh) = node.mesh() {
-            for primitive in mesh.primitives() {
-                Self::read_mesh(output, &primitive, buffers, name);
+            for (i, primitive) in mesh.primitives().enumerate() {
+                let mesh_id = Self::read_mesh(output, &mesh, &primitive, buffers, name);
 
                 let material = primitive.material();
-                Self::read_material(output, &material, buffers, name);
+                let material_id = Self::read_material(output, &material, buffers, name);
+
+                // every primitive is a mesh, so extra ones are added as child nodes
+                if i == 0 {
+                    let scene_node = &mut scene.nodes[index];
+                    scene_node.mesh = mesh_id;
+                    scene_node.material = material_id;
+                } else {
+                    scene.add(SceneNode {
+                        name: node.name().map(|node_name| format!("{}[{}]", node_name, i)),
+                        parent: Some(index),
+                        mesh: mesh_id,
+                        material: material_id,
+                        armature,
+                        ..Default::default()
+                    });
+                }
             }
         }
 
@@ -170,7 +248,15 @@ impl GltfLoader {
                 format!("{}.node[{}]", name, child.index())
             };
 
-            Self::read_node(output, &child, buffers, &child_name, root);
+            Self::read_node(
+                output,
+                scene,
+                &child,
+                buffers,
+                &child_name,
+                root,
+                Some(index),
+            );
         }
     }
 
@@ -180,10 +266,10 @@ impl GltfLoader {
         buffers: &[Vec<u8>],
         name: &str,
         root: Option<&gltf::Node>,
-    ) {
+    ) -> Option<Id<Armature>> {
         let skin_index = skin.index();
-        if output.loaded_armature.contains_key(&skin_index) {
-            return;
+        if let Some(armature_id) = output.loaded_armature.get(&skin_index) {
+            return Some(*armature_id);
         }
         let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
         let inverse_bind_matrices = reader.read_inverse_bind_matrices().map(|v| {
@@ -191,7 +277,9 @@ impl GltfLoader {
                 .collect::<Vec<_>>()
         });
 
-        let asset_name = [name, "armature"].join("::");
+        let asset_name = output.unique_name::<Armature>([name, "armature"].join("::"), || {
+            format!("{}::armature[{}]", name, skin_index)
+        });
 
         let index = skin
             .joints()
@@ -222,10 +310,9 @@ impl GltfLoader {
             armature.add(id, name.map(String::from), joint);
             output.loaded_joints.insert(index, id);
         }
-        output
-            .loaded_armature
-            .insert(skin_index, output.result.len());
-        output.result.push(Box::new(armature));
+        let armature_id = output.push_with_id(armature);
+        output.loaded_armature.insert(skin_index, armature_id);
+        Some(armature_id)
     }
 
     fn read_joints(
@@ -235,13 +322,7 @@ impl GltfLoader {
     ) {
         let id = Id::new();
 
-        let (translation, rotation, scale) = node.transform().decomposed();
-        let local_bind_transform = Transform::new(
-            Vec3::from(translation),
-            // Quat::new(rotation[3], rotation[0], rotation[1], rotation[2]),
-            Quat::from_xyzw(rotation[0], rotation[1], rotation[2], rotation[3]),
-            Vec3::from(scale),
-        );
+        let local_bind_transform = Self::read_transform(node);
         let index = node.index();
         let joint = Joint {
             parent_id,
@@ -256,15 +337,26 @@ impl GltfLoader {
         }
     }
 
+    fn read_transform(node: &gltf::Node) -> Transform {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        Transform::new(
+            Vec3::from(translation),
+            Quat::from_xyzw(rotation[0], rotation[1], rotation[2], rotation[3]),
+            Vec3::from(scale),
+        )
+    }
+
     fn read_mesh(
         output: &mut Output,
+        gltf_mesh: &gltf::Mesh,
         primitive: &gltf::Primitive,
         buffers: &[Vec<u8>],
         name: &str,
-    ) {
+    ) -> Option<Id<Mesh>> {
         let primitive_index = primitive.index();
-        if output.loaded_meshes.contains_key(&primitive_index) {
-            return;
+        let mesh_index = (gltf_mesh.index(), primitive_index);
+        if let Some(mesh_id) = output.loaded_meshes.get(&mesh_index) {
+            return Some(*mesh_id);
         }
 
         let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
@@ -272,10 +364,19 @@ impl GltfLoader {
 
         if mode != gltf::mesh::Mode::Triangles {
             log::error!("Unsupported topology: {:?}", mode);
-            return;
+            return None;
         };
 
-        let mut mesh = Mesh::new([name, "mesh"].join("::"));
+        let asset_name = if primitive_index == 0 {
+            [name, "mesh"].join("::")
+        } else {
+            format!("{}::mesh[{}]", name, primitive_index)
+        };
+        // e.g. meshes of root nodes share the name of the file
+        let asset_name = output.unique_name::<Mesh>(asset_name, || {
+            format!("{}::mesh[{}][{}]", name, gltf_mesh.index(), primitive_index)
+        });
+        let mut mesh = Mesh::new(asset_name);
 
         let indices = reader
             .read_indices()
@@ -308,10 +409,9 @@ impl GltfLoader {
             mesh.set_vertices::<VertexJoints>(joints.into_u16().collect::<Vec<[u16; 4]>>());
         }
 
-        output
-            .loaded_meshes
-            .insert(primitive_index, output.result.len());
-        output.result.push(Box::new(mesh));
+        let mesh_id = output.push_with_id(mesh);
+        output.loaded_meshes.insert(mesh_index, mesh_id);
+        Some(mesh_id)
     }
 
     fn read_material(
@@ -319,14 +419,10 @@ impl GltfLoader {
         material: &gltf::Material,
         buffers: &[Vec<u8>],
         name: &str,
-    ) {
+    ) -> Id<Material> {
         let material_index = material.index();
-        let already_loaded = material_index
-            .map(|index| output.loaded_materials.contains_key(&index))
-            .unwrap_or(false);
-
-        if already_loaded {
-            return;
+        if let Some(material_id) = output.loaded_materials.get(&material_index) {
+            return *material_id;
         }
         let pbr = material.pbr_metallic_roughness();
 
@@ -345,11 +441,24 @@ impl GltfLoader {
             .unwrap_or_default();
 
         let occlusion_map = material
-            .normal_texture()
+            .occlusion_texture()
             .map(|occlusion| Self::read_image(output, &occlusion.texture(), buffers, name))
             .unwrap_or_default();
 
-        let asset_name = [name, material.name().unwrap_or("material")].join("::");
+        // NOTE: glTF stores metallic (blue) and roughness (green) in the same texture
+        let metallic_roughness_map = pbr
+            .metallic_roughness_texture()
+            .map(|info| Self::read_image(output, &info.texture(), buffers, name))
+            .unwrap_or_default();
+
+        let asset_name = match (material.name(), material_index) {
+            (Some(material_name), Some(index)) => output
+                .unique_name::<Material>([name, material_name].join("::"), || {
+                    format!("{}::material[{}]", name, index)
+                }),
+            (None, Some(index)) => format!("{}::material[{}]", name, index),
+            (_, None) => [name, "material"].join("::"),
+        };
         let material_asset = Material {
             name: asset_name,
             albedo,
@@ -357,15 +466,16 @@ impl GltfLoader {
             normal_map,
             occlusion_map,
             metallic_factor,
+            metallic_map: metallic_roughness_map,
             roughness_factor,
+            roughness_map: metallic_roughness_map,
 
             ..Default::default()
         };
 
-        if let Some(index) = material_index {
-            output.loaded_materials.insert(index, output.result.len());
-        }
-        output.result.push(Box::new(material_asset));
+        let material_id = output.push_with_id(material_asset);
+        output.loaded_materials.insert(material_index, material_id);
+        material_id
     }
 
     fn read_image(
@@ -374,54 +484,60 @@ impl GltfLoader {
         buffers: &[Vec<u8>],
         name: &str,
     ) -> Id<Image> {
-        let asset_name = [name, texture.name().unwrap_or("material")].join("::");
         let image_index = texture.index();
-        let mut result_index = output.loaded_images.get(&image_index).cloned();
+        if let Some(image_id) = output.loaded_images.get(&image_index) {
+            return *image_id;
+        }
 
-        if result_index.is_none() {
-            let source = texture.source().source();
-            let (data, format) = match source {
-                gltf::image::Source::Uri { uri, .. } => {
-                    const URI_IMAGE_PNG: &str = "data:image/png;base64,";
+        let fallback = || format!("{}::texture[{}]", name, image_index);
+        let asset_name = match texture.name() {
+            Some(texture_name) => {
+                output.unique_name::<Image>([name, texture_name].join("::"), fallback)
+            }
+            None => fallback(),
+        };
 
-                    if !uri.starts_with(URI_IMAGE_PNG) {
-                        log::warn!("Unsupported texture uri");
-                        return Id::default();
-                    }
+        let source = texture.source().source();
+        let (data, format) = match source {
+            gltf::image::Source::Uri { uri, .. } => {
+                const URI_IMAGE_PNG: &str = "data:image/png;base64,";
 
-                    match base64_decode(&uri[URI_IMAGE_PNG.len()..]) {
-                        Ok(data) => (data, ImageFormat::Png),
-                        Err(e) => {
-                            log::error!("Could not decode texture data: {:?}", e);
-                            return Id::default();
-                        }
-                    }
+                if !uri.starts_with(URI_IMAGE_PNG) {
+                    log::warn!("Unsupported texture uri");
+                    return Id::default();
                 }
 
-                gltf::image::Source::View { view, mime_type } => {
-                    if mime_type != "image/png" {
-                        log::warn!("Unsupported mime: {}", mime_type);
+                match base64_decode(&uri[URI_IMAGE_PNG.len()..]) {
+                    Ok(data) => (data, ImageFormat::Png),
+                    Err(e) => {
+                        log::error!("Could not decode texture data: {:?}", e);
                         return Id::default();
                     }
+                }
+            }
 
-                    let index = view.buffer().index();
-                    let offset = view.offset();
-                    let tail = offset + view.length();
-                    let data = &buffers[index][offset..tail];
-
-                    (data.to_vec(), ImageFormat::Png)
+            gltf::image::Source::View { view, mime_type } => {
+                if mime_type != "image/png" {
+                    log::warn!("Unsupported mime: {}", mime_type);
+                    return Id::default();
                 }
-            };
 
-            if let Some(image) = ImageLoader::read_buffer(asset_name, &data, format) {
-                let index = output.result.len();
-                result_index.replace(index);
-                output.loaded_images.insert(image_index, index);
-                output.result.push(Box::new(image));
+                let index = view.buffer().index();
+                let offset = view.offset();
+                let tail = offset + view.length();
+                let data = &buffers[index][offset..tail];
+
+                (data.to_vec(), ImageFormat::Png)
             }
+        };
+
+        if let Some(image) = ImageLoader::read_buffer(asset_name, &data, format) {
+            let image_id = output.push_with_id(image);
+            output.loaded_images.insert(image_index, image_id);
+            return image_id;
         }
 
-        Id::new()
+        Id::default()
     }
 
     fn read_animation(
@@ -448,6 +564,7 @@ impl GltfLoader {
             };
             let index = channel.target().node().index();
             let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+            // NOTE: cubic spline outputs keep in and out tangents around every keyframe value
             let outputs = reader.read_outputs();
             let timestamps = reader.read_inputs().unwrap().collect::<Vec<f32>>();
             let joint_id = output
@@ -491,3 +608,110 @@ fn base64_decode<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, base64::DecodeErro
     let engine = base64::engine::general_purpose::STANDARD;
     engine.decode(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use base64::Engine;
+
+    use super::GltfLoader;
+    use crate::loaders::{Asset, Assets, ResourceLoader};
+    use crate::models::{Material, Mesh, Scene};
+    use crate::utils::Id;
+
+    /// Two unnamed root nodes with their own meshes and materials
+    fn two_root_meshes() -> String {
+        let positions: [[f32; 3]; 6] = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+        ];
+        let data = positions
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let data = base64::engine::general_purpose::STANDARD.encode(data);
+        let accessor = |count: usize| {
+            format!(
+                r#"{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","min":[0,0,0],"max":[1,1,0]}}"#,
+                count
+            )
+        };
+        format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "scene": 0,
+                "scenes": [{{"nodes": [0, 1]}}],
+                "nodes": [{{"mesh": 0}}, {{"mesh": 1}}],
+                "meshes": [
+                    {{"primitives": [{{"attributes": {{"POSITION": 0}}, "material": 0}}]}},
+                    {{"primitives": [{{"attributes": {{"POSITION": 1}}, "material": 1}}]}}
+                ],
+                "materials": [
+                    {{"pbrMetallicRoughness": {{"metallicFactor": 0.25}}}},
+                    {{"pbrMetallicRoughness": {{"metallicFactor": 0.75}}}}
+                ],
+                "buffers": [{{
+                    "byteLength": 72,
+                    "uri": "data:application/octet-stream;base64,{}"
+                }}],
+                "bufferViews": [{{"buffer": 0, "byteOffset": 0, "byteLength": 72}}],
+                "accessors": [{}, {}]
+            }}"#,
+            data,
+            accessor(3),
+            accessor(6)
+        )
+    }
+
+    #[test]
+    fn root_meshes_are_not_overwritten() {
+        let dir = std::env::temp_dir().join(format!("dotrix-{}", Id::<Scene>::new().uuid()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("quads.gltf");
+        std::fs::write(&path, two_root_meshes()).unwrap();
+        let bundle = GltfLoader.read(&path, &HashSet::new());
+        std::fs::remove_dir_all(&dir).ok();
+
+        let mut assets = Assets::new();
+        for (target, asset) in bundle.bundle {
+            let asset = asset.expect("Asset should be loaded");
+            match bundle.ids.get(&target) {
+                Some(&id) => assets.store_as(id, asset),
+                None => assets.store(asset),
+            };
+        }
+
+        let scene = assets
+            .find::<Scene>("quads::scene[0]")
+            .and_then(|id| assets.get(id))
+            .expect("Scene should be loaded");
+        let nodes = scene
+            .nodes
+            .iter()
+            .map(|node| {
+                let mesh = assets.get(node.mesh.unwrap()).unwrap();
+                let material = assets.get(node.material).unwrap();
+                (
+                    mesh.name().to_string(),
+                    mesh.count_vertices(),
+                    material.metallic_factor,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nodes,
+            vec![
+                (String::from("quads::mesh"), 3, 0.25),
+                (String::from("quads::mesh[1][0]"), 6, 0.75),
+            ]
+        );
+        assert!(assets.find::<Material>("quads::material[0]").is_some());
+        assert!(assets.find::<Mesh>("quads::mesh[1][0]").is_some());
+    }
+}
@@ -48,6 +48,7 @@ impl ResourceLoader for ImageLoader {
         ResourceBundle {
             resource: path.into(),
             bundle,
+            ids: HashMap::new(),
         }
     }
 }
@@ -1,5 +1,10 @@
 mod animations;
-pub use animations::{Animation, Interpolation};
+pub use animations::{add_pose, blend_poses, Animation, Interpolation, LocalPose};
+
+mod animators;
+pub use animators::{
+    Animate, Animated, AnimationLayer, AnimationPlayer, AnimationState, CrossFade, LayerMode, Pose,
+};
 
 mod armatures;
 pub use armatures::{Armature, Joint};
@@ -7,6 +12,12 @@ pub use armatures::{Armature, Joint};
 mod colors;
 pub use colors::Color;
 
+mod hierarchy;
+pub use hierarchy::{
+    attach, detach, propagate, Children, GlobalTransform, Parent, PropagateTransforms,
+    TransformsPropagated,
+};
+
 mod images;
 pub use images::{Image, ImageFormat};
 
@@ -21,6 +32,9 @@ pub use meshes::{
 mod renderer;
 pub use renderer::{RenderModels, RenderModelsSetup};
 
+mod scenes;
+pub use scenes::{Scene, SceneNode};
+
 mod transforms;
 pub use transforms::{Transform, TransformBuilder};
 
@@ -31,9 +45,19 @@ pub use vertices::{
 };
 
 use crate::math::{Quat, Vec3};
+use crate::tasks::Scheduler;
 use crate::utils::Id;
 use crate::world::Entity;
 
+/// Adds the renderer and tasks, it depends on, to the scheduler
+///
+/// This is the entry point for rendering models: [`RenderModels`] added alone does not keep
+/// [`GlobalTransform`] of entities in a hierarchy up to date.
+pub fn add_render_tasks(scheduler: &Scheduler, renderer: RenderModels) {
+    scheduler.add_task(PropagateTransforms::default());
+    scheduler.add_task(renderer);
+}
+
 pub struct Model {
     pub mesh: Id<Mesh>,
     pub material: Id<Material>,
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use super::{Joint, Transform, TransformBuilder};
+use super::{Armature, Joint, Transform, TransformBuilder};
 use crate::loaders::Asset;
-use crate::math::{Quat, Vec3};
+use crate::math::{Quat, Vec3, Vec4};
 use crate::utils::Id;
 
 pub struct Animation {
@@ -32,6 +32,9 @@ impl Animation {
     }
 
     /// Adds translation transformation channel
+    ///
+    /// For [`Interpolation::CubicSpline`] each timestamp has a triplet of in-tangent, value and
+    /// out-tangent
     pub fn add_translation_channel(
         &mut self,
         joint_id: Id<Joint>,
@@ -49,6 +52,9 @@ impl Animation {
     }
 
     /// Adds rotation transformation channel
+    ///
+    /// For [`Interpolation::CubicSpline`] each timestamp has a triplet of in-tangent, value and
+    /// out-tangent
     pub fn add_rotation_channel(
         &mut self,
         joint_id: Id<Joint>,
@@ -66,6 +72,9 @@ impl Animation {
     }
 
     /// Adds scale transformation channel
+    ///
+    /// For [`Interpolation::CubicSpline`] each timestamp has a triplet of in-tangent, value and
+    /// out-tangent
     pub fn add_scale_channel(
         &mut self,
         joint_id: Id<Joint>,
@@ -124,6 +133,81 @@ impl Animation {
 
         result
     }
+
+    /// Samples the animation at some keyframe (s) and returns local transformations of all
+    /// joints of the armature
+    ///
+    /// Joints without animation channels keep their local bind transformation
+    pub fn sample_pose(&self, keyframe: f32, armature: &Armature) -> LocalPose {
+        let mut sample = self.sample(keyframe);
+        armature
+            .joints
+            .iter()
+            .map(|(joint_id, joint)| {
+                let transform = sample
+                    .remove(joint_id)
+                    .map(|builder| joint.local_bind_transform.merge(&builder))
+                    .unwrap_or(joint.local_bind_transform);
+                (*joint_id, transform)
+            })
+            .collect()
+    }
+}
+
+/// Local transformations of armature joints
+pub type LocalPose = HashMap<Id<Joint>, Transform>;
+
+/// Blends poses using their weights
+///
+/// Weights are normalized, so they don't have to sum up to 1.0. If a joint is missing in some
+/// of poses, it is blended among the rest of them. Joints of a pose with zero weight are kept
+/// only if no other pose has them.
+pub fn blend_poses<'a>(poses: impl IntoIterator<Item = (&'a LocalPose, f32)>) -> LocalPose {
+    let mut result: HashMap<Id<Joint>, (Transform, f32)> = HashMap::new();
+    for (pose, weight) in poses {
+        let weight = weight.max(0.0);
+        for (joint_id, transform) in pose.iter() {
+            result
+                .entry(*joint_id)
+                .and_modify(|(blended, total_weight)| {
+                    *total_weight += weight;
+                    if *total_weight > 0.0 {
+                        let value = weight / *total_weight;
+                        *blended = interpolate_transform(blended, transform, value);
+                    }
+                })
+                .or_insert((*transform, weight));
+        }
+    }
+    result
+        .into_iter()
+        .map(|(joint_id, (transform, _))| (joint_id, transform))
+        .collect()
+}
+
+/// Adds difference between `additive` and `reference` poses to the `base` pose, scaled by weight
+///
+/// Joints missing in `additive` or `reference` poses are not affected.
+pub fn add_pose(base: &mut LocalPose, additive: &LocalPose, reference: &LocalPose, weight: f32) {
+    for (joint_id, transform) in base.iter_mut() {
+        let (Some(target), Some(origin)) = (additive.get(joint_id), reference.get(joint_id)) else {
+            continue;
+        };
+        let translate = target.translate - origin.translate;
+        let rotate = origin.rotate.inverse() * target.rotate;
+        let scale = target.scale / origin.scale;
+        transform.translate += Vec3::ZERO.linear(translate, weight);
+        transform.rotate = (transform.rotate * Quat::IDENTITY.linear(rotate, weight)).normalize();
+        transform.scale *= Vec3::ONE.linear(scale, weight);
+    }
+}
+
+fn interpolate_transform(from: &Transform, to: &Transform, value: f32) -> Transform {
+    Transform::new(
+        from.translate.linear(to.translate, value),
+        from.rotate.linear(to.rotate, value),
+        from.scale.linear(to.scale, value),
+    )
 }
 
 impl Asset for Animation {
@@ -133,7 +217,7 @@ impl Asset for Animation {
 }
 
 /// Interpolation types
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Interpolation {
     /// Linear interpolation
     Linear,
@@ -145,12 +229,48 @@ pub enum Interpolation {
 
 trait Interpolate: Copy {
     fn linear(self, target: Self, value: f32) -> Self;
+    /// Cubic Hermite spline between `self` and `target` as defined in glTF 2.0 specification
+    ///
+    /// `out_tangent` belongs to `self`, `in_tangent` belongs to `target`, `delta` is the time
+    /// between keyframes
+    fn cubic_spline(
+        self,
+        out_tangent: Self,
+        in_tangent: Self,
+        target: Self,
+        delta: f32,
+        value: f32,
+    ) -> Self;
+}
+
+/// Returns coefficients of the cubic Hermite spline basis functions
+fn hermite(delta: f32, value: f32) -> [f32; 4] {
+    let s2 = value * value;
+    let s3 = s2 * value;
+    [
+        2.0 * s3 - 3.0 * s2 + 1.0,
+        delta * (s3 - 2.0 * s2 + value),
+        -2.0 * s3 + 3.0 * s2,
+        delta * (s3 - s2),
+    ]
 }
 
 impl Interpolate for Vec3 {
     fn linear(self, target: Self, value: f32) -> Self {
         self.lerp(target, value)
     }
+
+    fn cubic_spline(
+        self,
+        out_tangent: Self,
+        in_tangent: Self,
+        target: Self,
+        delta: f32,
+        value: f32,
+    ) -> Self {
+        let [k0, k1, k2, k3] = hermite(delta, value);
+        k0 * self + k1 * out_tangent + k2 * target + k3 * in_tangent
+    }
 }
 
 impl Interpolate for Quat {
@@ -158,12 +278,30 @@ impl Interpolate for Quat {
         // NOTE: try slerp from math if any issue
         self.slerp(target, value)
     }
+
+    fn cubic_spline(
+        self,
+        out_tangent: Self,
+        in_tangent: Self,
+        target: Self,
+        delta: f32,
+        value: f32,
+    ) -> Self {
+        let [k0, k1, k2, k3] = hermite(delta, value);
+        let result = k0 * Vec4::from(self)
+            + k1 * Vec4::from(out_tangent)
+            + k2 * Vec4::from(target)
+            + k3 * Vec4::from(in_tangent);
+        Quat::from_vec4(result).normalize()
+    }
 }
 
 /// Keyframes for the channel transformations
 pub(crate) struct KeyFrame<T> {
     transformation: T,
     timestamp: f32,
+    /// In and out tangents of the cubic spline
+    tangents: Option<(T, T)>,
 }
 
 impl<T> KeyFrame<T> {
@@ -171,8 +309,14 @@ impl<T> KeyFrame<T> {
         Self {
             transformation,
             timestamp,
+            tangents: None,
         }
     }
+
+    fn with_tangents(mut self, in_tangent: T, out_tangent: T) -> Self {
+        self.tangents = Some((in_tangent, out_tangent));
+        self
+    }
 }
 
 struct Channel<T: Interpolate + Copy + Clone> {
@@ -182,17 +326,31 @@ struct Channel<T: Interpolate + Copy + Clone> {
 }
 
 impl<T: Interpolate + Copy + Clone> Channel<T> {
+    /// Constructs the channel
+    ///
+    /// For [`Interpolation::CubicSpline`] `transforms` must contain triplets of in-tangent,
+    /// value and out-tangent for each timestamp, as glTF stores them
     fn from(
         joint_id: Id<Joint>,
         interpolation: Interpolation,
         timestamps: Vec<f32>,
         transforms: Vec<T>,
     ) -> Self {
-        let keyframes = timestamps
-            .into_iter()
-            .zip(transforms)
-            .map(|(timestamp, transformation)| KeyFrame::new(timestamp, transformation))
-            .collect::<Vec<_>>();
+        let keyframes = if interpolation == Interpolation::CubicSpline {
+            timestamps
+                .into_iter()
+                .zip(transforms.chunks_exact(3))
+                .map(|(timestamp, triplet)| {
+                    KeyFrame::new(timestamp, triplet[1]).with_tangents(triplet[0], triplet[2])
+                })
+                .collect::<Vec<_>>()
+        } else {
+            timestamps
+                .into_iter()
+                .zip(transforms)
+                .map(|(timestamp, transformation)| KeyFrame::new(timestamp, transformation))
+                .collect::<Vec<_>>()
+        };
 
         Channel {
             keyframes,
@@ -202,21 +360,151 @@ impl<T: Interpolate + Copy + Clone> Channel<T> {
     }
 
     fn sample(&self, keyframe: f32) -> Option<T> {
-        for i in 0..self.keyframes.len() - 1 {
-            let first = &self.keyframes[i];
-            let next = &self.keyframes[i + 1];
-            if keyframe >= first.timestamp && keyframe < next.timestamp {
-                return match self.interpolation {
-                    Interpolation::Step => Some(first.transformation),
-                    Interpolation::Linear => {
-                        let value =
-                            (keyframe - first.timestamp) / (next.timestamp - first.timestamp);
-                        Some(first.transformation.linear(next.transformation, value))
-                    }
-                    _ => panic!("Unsupported interpolation {:?}", self.interpolation),
-                };
-            }
+        let first = self.keyframes.first()?;
+        if keyframe < first.timestamp {
+            return Some(first.transformation);
+        }
+        // index of the first keyframe after the sampled time
+        let next_index = self
+            .keyframes
+            .partition_point(|item| item.timestamp <= keyframe);
+        if next_index == self.keyframes.len() {
+            // keep the last keyframe after the end of the channel
+            return self.keyframes.last().map(|last| last.transformation);
         }
-        None
+        let first = &self.keyframes[next_index - 1];
+        let next = &self.keyframes[next_index];
+        let delta = next.timestamp - first.timestamp;
+        let value = (keyframe - first.timestamp) / delta;
+
+        Some(match self.interpolation {
+            Interpolation::Step => first.transformation,
+            Interpolation::Linear => first.transformation.linear(next.transformation, value),
+            Interpolation::CubicSpline => {
+                let (_, out_tangent) = first.tangents.expect("Keyframe must have tangents");
+                let (in_tangent, _) = next.tangents.expect("Keyframe must have tangents");
+                first.transformation.cubic_spline(
+                    out_tangent,
+                    in_tangent,
+                    next.transformation,
+                    delta,
+                    value,
+                )
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_pose, blend_poses, Channel, Interpolation, LocalPose};
+    use crate::math::{Quat, Vec3};
+    use crate::models::Transform;
+    use crate::utils::Id;
+
+    #[test]
+    fn poses_blend_by_normalized_weights() {
+        let joint = Id::new();
+        let partial = Id::new();
+        let idle = LocalPose::from([
+            (joint, Transform::from_translation(Vec3::new(0.0, 0.0, 0.0))),
+            (
+                partial,
+                Transform::from_translation(Vec3::new(1.0, 0.0, 0.0)),
+            ),
+        ]);
+        let walk = LocalPose::from([(
+            joint,
+            Transform::new(
+                Vec3::new(4.0, 0.0, 0.0),
+                Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+                Vec3::ONE,
+            ),
+        )]);
+
+        let pose = blend_poses([(&idle, 3.0), (&walk, 1.0)]);
+        let blended = pose[&joint];
+        assert!((blended.translate - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-5);
+        let expected = Quat::from_rotation_z(std::f32::consts::FRAC_PI_8);
+        assert!(blended.rotate.angle_between(expected) < 1e-4);
+        assert_eq!(pose[&partial].translate, Vec3::new(1.0, 0.0, 0.0));
+
+        let mut base = pose.clone();
+        add_pose(&mut base, &walk, &idle, 0.5);
+        assert!((base[&joint].translate - Vec3::new(3.0, 0.0, 0.0)).length() < 1e-5);
+        assert_eq!(base[&partial].translate, pose[&partial].translate);
+    }
+
+    #[test]
+    fn channels_sample_step_and_linear() {
+        let timestamps = vec![1.0, 2.0, 4.0];
+        let values = vec![Vec3::ZERO, Vec3::X, Vec3::new(3.0, 0.0, 0.0)];
+        let step = Channel::from(
+            Id::new(),
+            Interpolation::Step,
+            timestamps.clone(),
+            values.clone(),
+        );
+        let linear = Channel::from(Id::new(), Interpolation::Linear, timestamps, values);
+
+        assert_eq!(step.sample(0.0), Some(Vec3::ZERO));
+        assert_eq!(step.sample(1.9), Some(Vec3::ZERO));
+        assert_eq!(step.sample(2.0), Some(Vec3::X));
+        assert_eq!(step.sample(5.0), Some(Vec3::new(3.0, 0.0, 0.0)));
+
+        assert_eq!(linear.sample(1.5), Some(Vec3::new(0.5, 0.0, 0.0)));
+        assert_eq!(linear.sample(3.0), Some(Vec3::new(2.0, 0.0, 0.0)));
+        assert_eq!(linear.sample(4.0), Some(Vec3::new(3.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn channels_sample_cubic_spline() {
+        // triplets of in-tangent, value, out-tangent
+        let flat = Channel::from(
+            Id::new(),
+            Interpolation::CubicSpline,
+            vec![0.0, 1.0],
+            vec![
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::X,
+                Vec3::ZERO,
+            ],
+        );
+        // zero tangents give a smoothstep curve: 3s^2 - 2s^3
+        assert_eq!(flat.sample(0.5), Some(Vec3::new(0.5, 0.0, 0.0)));
+        assert_eq!(flat.sample(0.25), Some(Vec3::new(0.15625, 0.0, 0.0)));
+        assert_eq!(flat.sample(1.0), Some(Vec3::X));
+
+        let tangent = Channel::from(
+            Id::new(),
+            Interpolation::CubicSpline,
+            vec![0.0, 2.0],
+            vec![
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::Y,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+            ],
+        );
+        // delta * (s^3 - 2s^2 + s) * out_tangent
+        assert_eq!(tangent.sample(1.0), Some(Vec3::new(0.0, 0.25, 0.0)));
+
+        let zero = Quat::from_xyzw(0.0, 0.0, 0.0, 0.0);
+        let to = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let rotation = Channel::from(
+            Id::new(),
+            Interpolation::CubicSpline,
+            vec![0.0, 1.0],
+            vec![zero, Quat::IDENTITY, zero, zero, to, zero],
+        );
+        let sampled = rotation.sample(0.5).unwrap();
+        assert!(sampled.is_normalized());
+        let expected = Quat::from_rotation_z(std::f32::consts::FRAC_PI_4);
+        assert!(sampled.angle_between(expected) < 1e-4);
     }
 }
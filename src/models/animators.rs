@@ -0,0 +1,391 @@
+//! Animation playback components and task
+use std::collections::HashSet;
+use std::time::Duration;
+
+use super::{add_pose, blend_poses, Animation, Armature, Joint, LocalPose};
+use crate::loaders::Assets;
+use crate::math::Mat4;
+use crate::tasks::{Any, Mut, Ref, Task};
+use crate::utils::Id;
+use crate::world::World;
+use crate::{log, Frame};
+
+/// State of the animation playback
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AnimationState {
+    /// Animation time is being advanced every frame
+    Playing,
+    /// Animation time is frozen at current position
+    Paused,
+    /// Animation is stopped or reached its end
+    Stopped,
+}
+
+/// Animation playback component
+///
+/// Entity with the component must also have `Id<Armature>` and [`Pose`] components
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    /// Animation to be played
+    pub animation: Id<Animation>,
+    /// Playback state
+    pub state: AnimationState,
+    /// Restart the animation after it reaches its end
+    pub looped: bool,
+    /// Playback speed multiplier, negative values play the animation backwards
+    pub speed: f32,
+    /// Current position in the animation
+    pub time: Duration,
+    /// Cross-fade from the previously played animation
+    pub fade: Option<CrossFade>,
+    /// Layers applied on top of the animation
+    pub layers: Vec<AnimationLayer>,
+}
+
+impl AnimationPlayer {
+    /// Constructs a stopped player of the animation
+    pub fn new(animation: Id<Animation>) -> Self {
+        Self {
+            animation,
+            ..Default::default()
+        }
+    }
+
+    /// Sets looping of the animation
+    pub fn looped(mut self, looped: bool) -> Self {
+        self.looped = looped;
+        self
+    }
+
+    /// Sets playback speed multiplier
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Starts or resumes the playback
+    pub fn play(&mut self) {
+        self.state = AnimationState::Playing;
+    }
+
+    /// Pauses the playback
+    pub fn pause(&mut self) {
+        if self.state == AnimationState::Playing {
+            self.state = AnimationState::Paused;
+        }
+    }
+
+    /// Stops the playback and rewinds the animation
+    pub fn stop(&mut self) {
+        self.state = AnimationState::Stopped;
+        self.time = Duration::ZERO;
+    }
+
+    /// Sets current position in the animation
+    pub fn seek(&mut self, time: Duration) {
+        self.time = time;
+    }
+
+    /// Returns true if the animation is being played
+    pub fn is_playing(&self) -> bool {
+        self.state == AnimationState::Playing
+    }
+
+    /// Switches to another animation, smoothly fading out the current one during `duration`
+    pub fn cross_fade(&mut self, animation: Id<Animation>, duration: Duration) {
+        let from = AnimationPlayer {
+            animation: self.animation,
+            state: self.state,
+            looped: self.looped,
+            speed: self.speed,
+            time: self.time,
+            fade: self.fade.take(),
+            layers: Vec::new(),
+        };
+        self.fade = Some(CrossFade {
+            from: Box::new(from),
+            duration,
+            elapsed: Duration::ZERO,
+        });
+        self.animation = animation;
+        self.time = Duration::ZERO;
+        self.state = AnimationState::Playing;
+    }
+
+    /// Adds a layer on top of the animation
+    pub fn layer(mut self, layer: AnimationLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Advances the player, its cross-fade and layers by `delta`
+    pub fn update(&mut self, delta: Duration, assets: &Assets) {
+        if let Some(animation) = assets.get(self.animation) {
+            self.advance(delta, animation.duration());
+        }
+        if let Some(fade) = self.fade.as_mut() {
+            fade.from.update(delta, assets);
+            fade.elapsed += delta;
+            if fade.elapsed >= fade.duration {
+                self.fade = None;
+            }
+        }
+        for layer in self.layers.iter_mut() {
+            layer.player.update(delta, assets);
+        }
+    }
+
+    /// Samples local transformations of the armature joints at current position of the player
+    ///
+    /// Returns `None` if the animation is not loaded yet
+    pub fn sample(&self, assets: &Assets, armature: &Armature) -> Option<LocalPose> {
+        let animation = assets.get(self.animation)?;
+        let mut pose = animation.sample_pose(self.time.as_secs_f32(), armature);
+
+        if let Some(fade) = self.fade.as_ref() {
+            if let Some(from) = fade.from.sample(assets, armature) {
+                let weight = fade.weight();
+                pose = blend_poses([(&from, 1.0 - weight), (&pose, weight)]);
+            }
+        }
+
+        for layer in self.layers.iter().filter(|layer| layer.weight > 0.0) {
+            let layer_animation = match assets.get(layer.player.animation) {
+                Some(layer_animation) => layer_animation,
+                None => continue,
+            };
+            let mut layer_pose = match layer.player.sample(assets, armature) {
+                Some(layer_pose) => layer_pose,
+                None => continue,
+            };
+            if let Some(mask) = layer.mask.as_ref() {
+                layer_pose.retain(|joint_id, _| mask.contains(joint_id));
+            }
+            match layer.mode {
+                LayerMode::Blend => {
+                    pose = blend_poses([(&pose, 1.0 - layer.weight), (&layer_pose, layer.weight)]);
+                }
+                LayerMode::Additive => {
+                    let reference = layer_animation.sample_pose(0.0, armature);
+                    add_pose(&mut pose, &layer_pose, &reference, layer.weight);
+                }
+            }
+        }
+
+        Some(pose)
+    }
+
+    /// Advances current position of the player by `delta` using the animation `duration`
+    pub fn advance(&mut self, delta: Duration, duration: Duration) {
+        if self.state != AnimationState::Playing {
+            return;
+        }
+        let duration = duration.as_secs_f32();
+        if duration <= 0.0 {
+            self.time = Duration::ZERO;
+            return;
+        }
+        let time = self.time.as_secs_f32() + delta.as_secs_f32() * self.speed;
+        let time = if self.looped {
+            time.rem_euclid(duration)
+        } else if (self.speed > 0.0 && time >= duration) || (self.speed < 0.0 && time <= 0.0) {
+            self.state = AnimationState::Stopped;
+            time.clamp(0.0, duration)
+        } else {
+            time
+        };
+        self.time = Duration::from_secs_f32(time);
+    }
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self {
+            animation: Id::null(),
+            state: AnimationState::Stopped,
+            looped: false,
+            speed: 1.0,
+            time: Duration::ZERO,
+            fade: None,
+            layers: Vec::new(),
+        }
+    }
+}
+
+/// Cross-fade between two animations
+#[derive(Debug, Clone)]
+pub struct CrossFade {
+    /// Player of the animation being faded out
+    pub from: Box<AnimationPlayer>,
+    /// Duration of the cross-fade
+    pub duration: Duration,
+    /// Time passed since the cross-fade start
+    pub elapsed: Duration,
+}
+
+impl CrossFade {
+    /// Returns weight of the animation being faded in
+    pub fn weight(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).clamp(0.0, 1.0)
+    }
+}
+
+/// Mode of the animation layer
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LayerMode {
+    /// Layer pose is blended with the underlying one
+    Blend,
+    /// Difference between layer pose and its first keyframe is added to the underlying pose
+    Additive,
+}
+
+/// Animation layer, played on top of the [`AnimationPlayer`] animation
+#[derive(Debug, Clone)]
+pub struct AnimationLayer {
+    /// Player of the layer animation
+    pub player: AnimationPlayer,
+    /// Weight of the layer in range 0.0..=1.0
+    pub weight: f32,
+    /// Blending mode of the layer
+    pub mode: LayerMode,
+    /// Joints affected by the layer, all joints if `None`
+    pub mask: Option<HashSet<Id<Joint>>>,
+}
+
+impl AnimationLayer {
+    /// Constructs a layer blending the player animation with full weight
+    pub fn new(player: AnimationPlayer) -> Self {
+        Self {
+            player,
+            weight: 1.0,
+            mode: LayerMode::Blend,
+            mask: None,
+        }
+    }
+
+    /// Sets weight of the layer
+    pub fn weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets blending mode of the layer
+    pub fn mode(mut self, mode: LayerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Limits the layer to the joints
+    pub fn mask(mut self, joints: impl IntoIterator<Item = Id<Joint>>) -> Self {
+        self.mask = Some(joints.into_iter().collect());
+        self
+    }
+}
+
+/// Pose of the armature component
+///
+/// Used by the renderer instead of the bind pose of the armature
+#[derive(Debug, Default, Clone)]
+pub struct Pose {
+    /// Skinning matrices of joints in order of [`Armature::index`]
+    pub joint_matrices: Vec<Mat4>,
+}
+
+/// Output of the [`Animate`] task
+pub struct Animated {
+    /// Number of updated poses
+    pub count: usize,
+}
+
+/// Task, responsible for animations playback
+#[derive(Default)]
+pub struct Animate {}
+
+impl Task for Animate {
+    type Context = (Any<Frame>, Ref<Assets>, Mut<World>);
+    type Output = Animated;
+
+    fn run(&mut self, (frame, assets, world): Self::Context) -> Self::Output {
+        let mut count = 0;
+        for (armature_id, player, pose) in
+            world.query::<(&Id<Armature>, &mut AnimationPlayer, &mut Pose)>()
+        {
+            let armature = match assets.get(*armature_id) {
+                Some(armature) => armature,
+                None => continue,
+            };
+
+            player.update(frame.delta, &assets);
+
+            let local_pose = match player.sample(&assets, armature) {
+                Some(local_pose) => local_pose,
+                None => continue,
+            };
+
+            pose.joint_matrices = armature.joint_matrices(|joint_id, joint| {
+                local_pose
+                    .get(joint_id)
+                    .unwrap_or(&joint.local_bind_transform)
+                    .matrix()
+            });
+            count += 1;
+        }
+        log::debug!("Animate: {} poses updated", count);
+        Animated { count }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnimationPlayer, AnimationState};
+    use crate::utils::Id;
+    use std::time::Duration;
+
+    #[test]
+    fn player_loops_and_stops() {
+        let duration = Duration::from_secs(2);
+        let mut player = AnimationPlayer::new(Id::null()).looped(true);
+
+        player.advance(Duration::from_secs(1), duration);
+        assert_eq!(player.time, Duration::ZERO);
+
+        player.play();
+        player.advance(Duration::from_millis(2500), duration);
+        assert!((player.time.as_secs_f32() - 0.5).abs() < 1e-4);
+
+        player.looped = false;
+        player.advance(Duration::from_secs(3), duration);
+        assert_eq!(player.time, duration);
+        assert_eq!(player.state, AnimationState::Stopped);
+
+        player.speed = -1.0;
+        player.play();
+        player.advance(Duration::from_millis(500), duration);
+        assert!((player.time.as_secs_f32() - 1.5).abs() < 1e-4);
+        assert!(player.is_playing());
+    }
+
+    #[test]
+    fn player_keeps_playing_after_zero_delta() {
+        let duration = Duration::from_secs(2);
+        let mut player = AnimationPlayer::new(Id::null());
+        player.play();
+
+        // the first frame after start may have zero delta
+        player.advance(Duration::ZERO, duration);
+        assert!(player.is_playing());
+        player.advance(Duration::from_secs(1), duration);
+        assert_eq!(player.time, Duration::from_secs(1));
+
+        // reversed player stops at the beginning only
+        player.speed = -1.0;
+        player.advance(Duration::from_millis(500), duration);
+        assert!(player.is_playing());
+        player.advance(Duration::from_secs(1), duration);
+        assert_eq!(player.time, Duration::ZERO);
+        assert_eq!(player.state, AnimationState::Stopped);
+    }
+}
@@ -31,6 +31,85 @@ impl Armature {
         self.joints.insert(id, joint);
         id
     }
+
+    /// Returns the joint and all its descendants, useful for masking of animation layers
+    pub fn branch(&self, root_id: Id<Joint>) -> Vec<Id<Joint>> {
+        let mut branch = vec![root_id];
+        let mut i = 0;
+        while i < branch.len() {
+            let parent_id = branch[i];
+            branch.extend(
+                self.index
+                    .iter()
+                    .filter(|id| self.joints[*id].parent_id == Some(parent_id)),
+            );
+            i += 1;
+        }
+        branch
+    }
+
+    /// Returns global transformation matrices of joints in order of the index
+    ///
+    /// `local` returns local transformation matrix of a joint. Joints with a parent that does not
+    /// belong to the armature are handled as roots.
+    pub fn global_transforms<F>(&self, local: F) -> Vec<Mat4>
+    where
+        F: Fn(&Id<Joint>, &Joint) -> Mat4,
+    {
+        let mut globals: HashMap<Id<Joint>, Mat4> = HashMap::with_capacity(self.index.len());
+        let mut branch = Vec::new();
+
+        for joint_id in self.index.iter() {
+            // collect not yet calculated joints from the current one up to the root
+            let mut parent = Mat4::IDENTITY;
+            let mut next_id = Some(*joint_id);
+            while let Some(id) = next_id {
+                if let Some(global) = globals.get(&id) {
+                    parent = *global;
+                    break;
+                }
+                next_id = self.joints.get(&id).and_then(|joint| {
+                    branch.push((id, joint));
+                    joint.parent_id
+                });
+            }
+            // calculate global transformations from the root down to the current joint
+            for (id, joint) in branch.drain(..).rev() {
+                parent *= local(&id, joint);
+                globals.insert(id, parent);
+            }
+        }
+
+        self.index
+            .iter()
+            .map(|id| globals.get(id).copied().unwrap_or(Mat4::IDENTITY))
+            .collect()
+    }
+
+    /// Returns skinning matrices of joints in order of the index
+    ///
+    /// `local` returns local transformation matrix of a joint, see [`Armature::global_transforms`]
+    pub fn joint_matrices<F>(&self, local: F) -> Vec<Mat4>
+    where
+        F: Fn(&Id<Joint>, &Joint) -> Mat4,
+    {
+        self.global_transforms(local)
+            .into_iter()
+            .zip(self.index.iter())
+            .map(|(global, id)| {
+                self.joints
+                    .get(id)
+                    .and_then(|joint| joint.inverse_bind_matrix)
+                    .map(|inverse_bind_matrix| global * inverse_bind_matrix)
+                    .unwrap_or(global)
+            })
+            .collect()
+    }
+
+    /// Returns skinning matrices of joints in the bind pose
+    pub fn bind_pose(&self) -> Vec<Mat4> {
+        self.joint_matrices(|_, joint| joint.local_bind_transform.matrix())
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -49,3 +128,55 @@ impl Asset for Armature {
         &self.name
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Armature, Joint};
+    use crate::math::{Mat4, Vec3};
+    use crate::models::Transform;
+    use crate::utils::Id;
+
+    #[test]
+    fn global_transforms_follow_hierarchy() {
+        let mut armature = Armature::new(String::from("test"), 3);
+        let root = Id::new();
+        let child = Id::new();
+        let leaf = Id::new();
+        // index order does not follow the hierarchy on purpose
+        armature.add(
+            leaf,
+            None,
+            Joint {
+                local_bind_transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
+                inverse_bind_matrix: None,
+                parent_id: Some(child),
+            },
+        );
+        armature.add(
+            root,
+            None,
+            Joint {
+                local_bind_transform: Transform::from_translation(Vec3::new(1.0, 0.0, 0.0)),
+                inverse_bind_matrix: Some(Mat4::from_translation(Vec3::new(-1.0, 0.0, 0.0))),
+                parent_id: None,
+            },
+        );
+        armature.add(
+            child,
+            None,
+            Joint {
+                local_bind_transform: Transform::from_translation(Vec3::new(0.0, 2.0, 0.0)),
+                inverse_bind_matrix: None,
+                parent_id: Some(root),
+            },
+        );
+
+        let globals = armature.global_transforms(|_, joint| joint.local_bind_transform.matrix());
+        assert_eq!(globals[0], Mat4::from_translation(Vec3::new(1.0, 2.0, 1.0)));
+        assert_eq!(globals[1], Mat4::from_translation(Vec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(globals[2], Mat4::from_translation(Vec3::new(1.0, 2.0, 0.0)));
+
+        let bind_pose = armature.bind_pose();
+        assert_eq!(bind_pose[1], Mat4::IDENTITY);
+    }
+}
@@ -0,0 +1,318 @@
+//! Hierarchy of entities and propagation of transformations
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Armature, Joint, Pose, Transform};
+use crate::loaders::Assets;
+use crate::log;
+use crate::math::Mat4;
+use crate::tasks::{Mut, Ref, Task};
+use crate::utils::Id;
+use crate::world::{Entity, With, Without, World};
+
+/// Parent of the entity
+///
+/// [`Transform`] of the entity is relative to the parent, or to the joint of the parent armature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parent {
+    /// Parent entity
+    pub entity: Id<Entity>,
+    /// Joint of the parent armature
+    pub joint: Option<Id<Joint>>,
+}
+
+impl Parent {
+    /// Constructs the parent component
+    pub fn new(entity: Id<Entity>) -> Self {
+        Self {
+            entity,
+            joint: None,
+        }
+    }
+
+    /// Attaches the entity to the joint of the parent armature
+    #[must_use]
+    pub fn joint(mut self, joint: Id<Joint>) -> Self {
+        self.joint = Some(joint);
+        self
+    }
+}
+
+/// Children of the entity, maintained by [`attach`] and [`detach`]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Children(pub Vec<Id<Entity>>);
+
+/// Global transformation of the entity, calculated by [`PropagateTransforms`]
+///
+/// The renderer uses it instead of [`Transform`], if the entity has one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform {
+    /// Transformation matrix in the world space
+    pub matrix: Mat4,
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Mat4::IDENTITY,
+        }
+    }
+}
+
+/// Attaches the `child` entity to the parent
+///
+/// Returns false if one of entities does not exist or the parent is a descendant of the child.
+pub fn attach(world: &mut World, child: Id<Entity>, parent: Parent) -> bool {
+    if !world.contains(&child) || !world.contains(&parent.entity) {
+        return false;
+    }
+
+    let parents = world
+        .query::<(&Id<Entity>, &Parent)>()
+        .map(|(id, parent)| (*id, parent.entity))
+        .collect::<HashMap<_, _>>();
+    let mut ancestor = Some(parent.entity);
+    while let Some(id) = ancestor {
+        if id == child {
+            return false;
+        }
+        ancestor = parents.get(&id).copied();
+    }
+
+    detach(world, child);
+    world.insert(&child, parent);
+    if world.component_mut::<GlobalTransform>(&child).is_none() {
+        world.insert(&child, GlobalTransform::default());
+    }
+    match world.component_mut::<Children>(&parent.entity) {
+        Some(children) => children.0.push(child),
+        None => {
+            world.insert(&parent.entity, Children(vec![child]));
+        }
+    }
+    true
+}
+
+/// Detaches the `child` entity from its parent and returns the removed [`Parent`] component
+pub fn detach(world: &mut World, child: Id<Entity>) -> Option<Parent> {
+    let parent = world.remove::<Parent>(&child)?;
+    world.remove::<GlobalTransform>(&child);
+    if let Some(children) = world.component_mut::<Children>(&parent.entity) {
+        children.0.retain(|id| *id != child);
+    }
+    Some(parent)
+}
+
+/// Output of the [`PropagateTransforms`] task
+pub struct TransformsPropagated {
+    /// Number of updated global transformations
+    pub count: usize,
+}
+
+/// Task, calculating [`GlobalTransform`] of entities
+///
+/// Entities with a [`Parent`], that does not exist anymore, are handled as roots.
+#[derive(Default)]
+pub struct PropagateTransforms {}
+
+impl Task for PropagateTransforms {
+    type Context = (Ref<Assets>, Mut<World>);
+    type Output = TransformsPropagated;
+
+    fn run(&mut self, (assets, mut world): Self::Context) -> Self::Output {
+        let detached = world
+            .query::<(&Id<Entity>, With<Parent>, Without<GlobalTransform>)>()
+            .map(|(id, _, _)| *id)
+            .collect::<Vec<_>>();
+        for id in detached.iter() {
+            world.insert(id, GlobalTransform::default());
+        }
+
+        let count = propagate(&world, &assets);
+        log::debug!("PropagateTransforms: {} transforms updated", count);
+        TransformsPropagated { count }
+    }
+}
+
+/// Calculates global transformations of entities and returns number of updated ones
+pub fn propagate(world: &World, assets: &Assets) -> usize {
+    let locals = world
+        .query::<(&Id<Entity>, &Transform)>()
+        .map(|(id, transform)| (*id, transform.matrix()))
+        .collect::<HashMap<_, _>>();
+    let parents = world
+        .query::<(&Id<Entity>, &Parent)>()
+        .filter(|(_, parent)| world.contains(&parent.entity))
+        .map(|(id, parent)| (*id, *parent))
+        .collect::<HashMap<_, _>>();
+    let joints = joint_transforms(world, assets, &parents);
+
+    let mut hierarchy = Hierarchy {
+        locals,
+        parents,
+        joints,
+        globals: HashMap::new(),
+    };
+
+    let mut count = 0;
+    for (id, global_transform) in world.query::<(&Id<Entity>, &mut GlobalTransform)>() {
+        global_transform.matrix = hierarchy.global(*id);
+        count += 1;
+    }
+    count
+}
+
+/// Returns transformations of joints, entities are attached to, in the space of the armature
+fn joint_transforms(
+    world: &World,
+    assets: &Assets,
+    parents: &HashMap<Id<Entity>, Parent>,
+) -> HashMap<(Id<Entity>, Id<Joint>), Mat4> {
+    let mut joints = HashMap::new();
+    let requests = parents
+        .values()
+        .filter_map(|parent| parent.joint.map(|joint| (parent.entity, joint)))
+        .collect::<HashSet<_>>();
+    if requests.is_empty() {
+        return joints;
+    }
+
+    for (id, armature_id, pose) in world.query::<(&Id<Entity>, &Id<Armature>, Option<&Pose>)>() {
+        let armature = match assets.get(*armature_id) {
+            Some(armature) => armature,
+            None => continue,
+        };
+        let pose = pose.filter(|pose| pose.joint_matrices.len() == armature.index.len());
+        let mut bind_pose = None;
+        for (index, joint_id) in armature.index.iter().enumerate() {
+            if !requests.contains(&(*id, *joint_id)) {
+                continue;
+            }
+            let matrix = match pose {
+                // skinning matrix is multiplied by the inverse bind matrix
+                Some(pose) => armature
+                    .joints
+                    .get(joint_id)
+                    .and_then(|joint| joint.inverse_bind_matrix)
+                    .map(|inverse_bind_matrix| {
+                        pose.joint_matrices[index] * inverse_bind_matrix.inverse()
+                    })
+                    .unwrap_or(pose.joint_matrices[index]),
+                None => bind_pose.get_or_insert_with(|| {
+                    armature.global_transforms(|_, joint| joint.local_bind_transform.matrix())
+                })[index],
+            };
+            joints.insert((*id, *joint_id), matrix);
+        }
+    }
+    joints
+}
+
+/// Cache of global transformations
+struct Hierarchy {
+    locals: HashMap<Id<Entity>, Mat4>,
+    parents: HashMap<Id<Entity>, Parent>,
+    joints: HashMap<(Id<Entity>, Id<Joint>), Mat4>,
+    globals: HashMap<Id<Entity>, Mat4>,
+}
+
+impl Hierarchy {
+    fn global(&mut self, id: Id<Entity>) -> Mat4 {
+        // collect not yet calculated entities from the current one up to the root
+        let mut branch = Vec::new();
+        let mut global = Mat4::IDENTITY;
+        let mut next = Some(id);
+        while let Some(current) = next {
+            if let Some(parent_global) = self.globals.get(&current) {
+                global = *parent_global;
+                break;
+            }
+            if branch.len() > self.parents.len() {
+                log::error!("Hierarchy of {:?} has a cycle", id);
+                break;
+            }
+            branch.push(current);
+            next = self.parents.get(&current).map(|parent| parent.entity);
+        }
+
+        // calculate global transformations from the root down to the current entity
+        for current in branch.into_iter().rev() {
+            if let Some(joint) = self.parents.get(&current).and_then(|parent| {
+                parent
+                    .joint
+                    .and_then(|joint| self.joints.get(&(parent.entity, joint)))
+            }) {
+                global *= *joint;
+            }
+            if let Some(local) = self.locals.get(&current) {
+                global *= *local;
+            }
+            self.globals.insert(current, global);
+        }
+        global
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attach, detach, propagate, Children, GlobalTransform, Parent};
+    use crate::loaders::Assets;
+    use crate::math::{Mat4, Quat, Vec3};
+    use crate::models::Transform;
+    use crate::world::World;
+
+    #[test]
+    fn transforms_are_propagated() {
+        let mut world = World::new();
+        let mut ids = world
+            .spawn([
+                (Transform::from_translation(Vec3::new(1.0, 0.0, 0.0)),),
+                (Transform::from_rotation(Quat::from_rotation_z(
+                    std::f32::consts::FRAC_PI_2,
+                )),),
+                (Transform::from_translation(Vec3::new(0.0, 2.0, 0.0)),),
+            ])
+            .collect::<Vec<_>>()
+            .into_iter();
+        let (tank, turret, barrel) = (
+            ids.next().unwrap(),
+            ids.next().unwrap(),
+            ids.next().unwrap(),
+        );
+
+        assert!(attach(&mut world, barrel, Parent::new(turret)));
+        assert!(attach(&mut world, turret, Parent::new(tank)));
+        // cycles are not allowed
+        assert!(!attach(&mut world, tank, Parent::new(barrel)));
+
+        let assets = Assets::default();
+        assert_eq!(propagate(&world, &assets), 2);
+        let global = |world: &mut World, id| {
+            world
+                .component_mut::<GlobalTransform>(&id)
+                .map(|global| global.matrix)
+        };
+        let barrel_position = global(&mut world, barrel)
+            .unwrap()
+            .transform_point3(Vec3::ZERO);
+        assert!(barrel_position.abs_diff_eq(Vec3::new(-1.0, 0.0, 0.0), 1e-5));
+        assert_eq!(
+            world.component_mut::<Children>(&tank),
+            Some(&mut Children(vec![turret]))
+        );
+
+        assert_eq!(detach(&mut world, turret), Some(Parent::new(tank)));
+        assert_eq!(global(&mut world, turret), None);
+        assert_eq!(
+            world.component_mut::<Children>(&tank),
+            Some(&mut Children(vec![]))
+        );
+        propagate(&world, &assets);
+        let turret_rotation = Mat4::from_quat(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        assert!(global(&mut world, barrel).unwrap().abs_diff_eq(
+            turret_rotation * Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0)),
+            1e-5
+        ));
+    }
+}
@@ -9,6 +9,7 @@ pub enum ImageFormat {
 }
 
 // Image in RGBA8 format
+#[derive(Clone)]
 pub struct Image {
     /// Image name
     name: String,
@@ -37,6 +38,17 @@ impl Image {
     pub fn data(&self) -> &[u8] {
         self.data.as_slice()
     }
+
+    /// Saves image to a file, format is defined by the path extension
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), image::ImageError> {
+        image::save_buffer(
+            path,
+            &self.data,
+            self.resolution.width,
+            self.resolution.height,
+            image::ExtendedColorType::Rgba8,
+        )
+    }
 }
 
 impl Asset for Image {
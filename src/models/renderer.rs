@@ -1,20 +1,19 @@
 use std::collections::HashMap;
-use std::ffi::CStr;
 use std::io::Cursor;
 
 use crate::graphics::vk;
-use crate::graphics::{Buffer, RenderPass};
+use crate::graphics::{Buffer, RenderPass, Texture};
 use crate::loaders::Assets;
-use crate::math::{Mat4, Vec3};
+use crate::math::Mat4;
 use crate::utils::Id;
-use crate::world::{Entity, World};
+use crate::world::{Camera, Entity, Light, LightUniform, World};
 use crate::{log, VertexJoints, VertexWeights};
 use crate::{Any, Asset, Display, Extent2D, Frame, Gpu, Ref, Task};
 
 use super::materials::MaterialUniform;
 use super::{
-    Armature, Material, Mesh, Transform, VertexBufferLayout, VertexNormal, VertexPosition,
-    VertexTexture,
+    Armature, GlobalTransform, Image, Material, Mesh, Pose, Transform, VertexBufferLayout,
+    VertexNormal, VertexPosition, VertexTexture,
 };
 
 #[derive(Clone, Copy)]
@@ -45,6 +44,20 @@ pub struct DrawCount {
     pub skin_mesh_indexed: u32,
 }
 
+/// Offsets of draw commands in the indirect buffer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DrawOffset {
+    pub only_mesh: u64,
+    pub only_mesh_indexed: u64,
+    pub skin_mesh: u64,
+    pub skin_mesh_indexed: u64,
+}
+
+/// Task, rendering models of the world
+///
+/// Entities without [`GlobalTransform`] are rendered with their local [`Transform`]. Global
+/// transforms are kept up to date by [`super::PropagateTransforms`], add both tasks with
+/// [`super::add_render_tasks`].
 pub struct RenderModels {
     /// GPU instance
     gpu: Gpu,
@@ -98,6 +111,18 @@ pub struct RenderModels {
     materials_buffer_index: HashMap<Id<Material>, u32>,
     /// Materials buffer data
     materials_buffer_data: Vec<MaterialUniform>,
+    /// Lights buffer
+    lights_buffer: Buffer,
+    /// Joints buffer (skinning matrices of rigged instances)
+    joints_buffer: Buffer,
+    /// Sampler of material textures
+    sampler: vk::Sampler,
+    /// Material textures, the first one is a default white texture
+    textures: Vec<Texture>,
+    /// Mapping of texture index in the descriptor array by image ID and format
+    textures_index: HashMap<(Id<Image>, vk::Format), u32>,
+    /// Maximal number of textures in the descriptor array
+    max_textures: u32,
     /// Mesh layouts of non-rigged models
     mesh_registry: HashMap<Id<Mesh>, MeshLayout>,
     /// descriptor sets
@@ -114,6 +139,12 @@ pub struct RenderModels {
     shader_vertex_non_rigged: vk::ShaderModule,
     /// Fragment shader module for non-rigged pipeline
     shader_fragment_non_rigged: vk::ShaderModule,
+    /// Pipeline layout to render rigged models
+    pipeline_layout_render_rigged: vk::PipelineLayout,
+    /// Graphics pipeline to render rigged models
+    pipeline_render_rigged: vk::Pipeline,
+    /// Vertex shader module for rigged pipeline
+    shader_vertex_rigged: vk::ShaderModule,
 }
 
 pub type VertexBufferOnlyMeshLayout = (VertexPosition, VertexNormal, VertexTexture);
@@ -136,12 +167,15 @@ impl Drop for RenderModels {
             // pipelines layouts
             self.gpu
                 .destroy_pipeline_layout(self.pipeline_layout_render_non_rigged);
+            self.gpu
+                .destroy_pipeline_layout(self.pipeline_layout_render_rigged);
 
             // shaders
             self.gpu
                 .destroy_shader_module(self.shader_vertex_non_rigged);
             self.gpu
                 .destroy_shader_module(self.shader_fragment_non_rigged);
+            self.gpu.destroy_shader_module(self.shader_vertex_rigged);
 
             // buffers
             self.globals_buffer.free_memory_and_destroy(&self.gpu);
@@ -153,6 +187,14 @@ impl Drop for RenderModels {
             self.indirect_buffer.free_memory_and_destroy(&self.gpu);
             self.instance_buffer.free_memory_and_destroy(&self.gpu);
             self.materials_buffer.free_memory_and_destroy(&self.gpu);
+            self.lights_buffer.free_memory_and_destroy(&self.gpu);
+            self.joints_buffer.free_memory_and_destroy(&self.gpu);
+
+            // textures
+            for texture in self.textures.iter() {
+                texture.free_memory_and_destroy(&self.gpu);
+            }
+            self.gpu.destroy_sampler(self.sampler);
 
             // descriptors
             for &descriptor_set_layout in self.desc_set_layouts.iter() {
@@ -182,12 +224,23 @@ impl Drop for RenderModels {
 }
 
 impl Task for RenderModels {
-    type Context = (Any<Frame>, Ref<Assets>, Ref<Display>, Ref<World>);
+    type Context = (
+        Any<Frame>,
+        Ref<Assets>,
+        Ref<Camera>,
+        Ref<Display>,
+        Ref<World>,
+    );
     type Output = RenderPass;
 
-    fn run(&mut self, (frame, assets, display, world): Self::Context) -> Self::Output {
+    fn run(&mut self, (frame, assets, camera, display, world): Self::Context) -> Self::Output {
         log::debug!("pbr: begin");
 
+        if frame.skipped {
+            log::debug!("pbr: frame skipped");
+            return RenderPass {};
+        }
+
         if let Some(surface_version) = display.surface_changed(self.surface_version) {
             unsafe {
                 log::debug!("resize: Surface changed");
@@ -206,25 +259,27 @@ impl Task for RenderModels {
                     log::debug!("resize: destroy_graphics_pipelines");
                     self.destroy_graphics_pipelines();
                     log::debug!("resize: create_graphics_pipelines");
-                    self.pipeline_render_non_rigged =
+                    let (pipeline_render_non_rigged, pipeline_render_rigged) =
                         self.create_graphics_pipelines(display.surface_resolution());
-
-                    // NOTE: the setup buffer should be probably a part of the Display
-                    log::debug!("resize: setup_depth_image");
-                    self.setup_depth_image(&display);
+                    self.pipeline_render_non_rigged = pipeline_render_non_rigged;
+                    self.pipeline_render_rigged = pipeline_render_rigged;
                 }
 
+                // NOTE: the setup buffer should be probably a part of the Display
+                log::debug!("resize: setup_depth_image");
+                self.setup_depth_image(&display);
+
                 log::debug!("resize: complete -> {}", surface_version);
             };
             self.surface_version = surface_version;
         }
 
-        let draw_count = self.update_buffers(&assets, &world);
+        let (draw_count, draw_offset) = self.update_buffers(&frame, &assets, &camera, &world);
 
-        log::debug!("draw count: {:?}", draw_count);
+        log::debug!("draw count: {:?}, offset: {:?}", draw_count, draw_offset);
 
         unsafe {
-            self.execute_render_pass(&frame, draw_count);
+            self.execute_render_pass(&frame, draw_count, draw_offset);
             self.submit_draw_commands();
         }
 
@@ -283,7 +338,7 @@ impl RenderModels {
                 samples: vk::SampleCountFlags::TYPE_1,
                 load_op: vk::AttachmentLoadOp::CLEAR,
                 store_op: vk::AttachmentStoreOp::STORE,
-                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                final_layout: display.surface_final_layout(),
                 ..Default::default()
             },
             vk::AttachmentDescription {
@@ -356,6 +411,15 @@ impl RenderModels {
             Self::create_storage_buffer(&gpu, setup.instance_buffer_size)
                 .expect("Could not allocate instances storage buffer")
         };
+        let lights_buffer = unsafe {
+            Self::create_storage_buffer(&gpu, setup.lights_buffer_size)
+                .expect("Could not allocate lights storage buffer")
+        };
+
+        let joints_buffer = unsafe {
+            Self::create_storage_buffer(&gpu, setup.joints_buffer_size)
+                .expect("Could not allocate joints storage buffer")
+        };
 
         let shader_vertex_non_rigged = unsafe {
             Self::load_shader_module(&gpu, include_bytes!("shaders/non-rigged.vert.spv"))
@@ -365,6 +429,10 @@ impl RenderModels {
             Self::load_shader_module(&gpu, include_bytes!("shaders/non-rigged.frag.spv"))
                 .expect("Failed to load non-rigged fragment shader module")
         };
+        let shader_vertex_rigged = unsafe {
+            Self::load_shader_module(&gpu, include_bytes!("shaders/rigged.vert.spv"))
+                .expect("Failed to load rigged vertex shader module")
+        };
 
         // bindings layout
         let descriptor_sizes = [
@@ -382,10 +450,22 @@ impl RenderModels {
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::STORAGE_BUFFER,
                 descriptor_count: 1,
-            }, // vk::DescriptorPoolSize {
-               //    ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-               //    descriptor_count: 1,
-               // },
+            },
+            // Lights
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+            },
+            // Textures
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: setup.max_textures,
+            },
+            // Joints
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+            },
         ];
         let descriptor_pool_info = vk::DescriptorPoolCreateInfo::default()
             .pool_sizes(&descriptor_sizes)
@@ -409,21 +489,38 @@ impl RenderModels {
                 stage_flags: vk::ShaderStageFlags::VERTEX,
                 ..Default::default()
             },
+            // Materials
             vk::DescriptorSetLayoutBinding {
                 binding: 2,
                 descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
                 descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            // Lights
+            vk::DescriptorSetLayoutBinding {
+                binding: 3,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            // Textures
+            vk::DescriptorSetLayoutBinding {
+                binding: 4,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: setup.max_textures,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            // Joints
+            vk::DescriptorSetLayoutBinding {
+                binding: 5,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
                 stage_flags: vk::ShaderStageFlags::VERTEX,
                 ..Default::default()
             },
-            // Materials
-            //vk::DescriptorSetLayoutBinding {
-            //    binding: 1,
-            //    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-            //    descriptor_count: 1,
-            //    stage_flags: vk::ShaderStageFlags::FRAGMENT,
-            //    ..Default::default()
-            //},
         ];
         let descriptor_info =
             vk::DescriptorSetLayoutCreateInfo::default().bindings(&desc_layout_bindings);
@@ -454,11 +551,55 @@ impl RenderModels {
             range: materials_buffer.size,
         };
 
-        // let tex_descriptor = vk::DescriptorImageInfo {
-        //    image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-        //    image_view: tex_image_view,
-        //    sampler,
-        // };
+        let light_storage_buffer_descriptor = vk::DescriptorBufferInfo {
+            buffer: lights_buffer.handle,
+            offset: 0,
+            range: lights_buffer.size,
+        };
+
+        let joint_storage_buffer_descriptor = vk::DescriptorBufferInfo {
+            buffer: joints_buffer.handle,
+            offset: 0,
+            range: joints_buffer.size,
+        };
+
+        let sampler_create_info = vk::SamplerCreateInfo::default()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+            .address_mode_u(vk::SamplerAddressMode::REPEAT)
+            .address_mode_v(vk::SamplerAddressMode::REPEAT)
+            .address_mode_w(vk::SamplerAddressMode::REPEAT)
+            .max_lod(vk::LOD_CLAMP_NONE)
+            .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE);
+
+        let sampler = unsafe {
+            gpu.create_sampler(&sampler_create_info)
+                .expect("Failed to create a sampler")
+        };
+
+        let default_texture = unsafe {
+            Self::upload_texture(
+                &gpu,
+                command_buffer_setup,
+                command_buffer_setup_reuse_fence,
+                Extent2D {
+                    width: 1,
+                    height: 1,
+                },
+                vk::Format::R8G8B8A8_UNORM,
+                &[255, 255, 255, 255],
+            )
+            .expect("Could not create default texture")
+        };
+
+        let tex_descriptors = (0..setup.max_textures)
+            .map(|_| vk::DescriptorImageInfo {
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                image_view: default_texture.image_view,
+                sampler,
+            })
+            .collect::<Vec<_>>();
 
         let write_desc_sets = [
             vk::WriteDescriptorSet {
@@ -485,14 +626,27 @@ impl RenderModels {
                 p_buffer_info: &material_storage_buffer_descriptor,
                 ..Default::default()
             },
-            //vk::WriteDescriptorSet {
-            //    dst_set: descriptor_sets[0],
-            //    dst_binding: 1,
-            //    descriptor_count: 1,
-            //    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-            //    p_image_info: &tex_descriptor,
-            //    ..Default::default()
-            //},
+            vk::WriteDescriptorSet {
+                dst_binding: 3,
+                dst_set: descriptor_sets[0],
+                descriptor_count: 1,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                p_buffer_info: &light_storage_buffer_descriptor,
+                ..Default::default()
+            },
+            vk::WriteDescriptorSet::default()
+                .dst_binding(4)
+                .dst_set(descriptor_sets[0])
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(&tex_descriptors),
+            vk::WriteDescriptorSet {
+                dst_binding: 5,
+                dst_set: descriptor_sets[0],
+                descriptor_count: 1,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                p_buffer_info: &joint_storage_buffer_descriptor,
+                ..Default::default()
+            },
         ];
 
         unsafe {
@@ -506,6 +660,10 @@ impl RenderModels {
             gpu.create_pipeline_layout(&pipeline_layout_create_info)
                 .expect("Failed to create non-rigged pipeline layout")
         };
+        let pipeline_layout_render_rigged = unsafe {
+            gpu.create_pipeline_layout(&pipeline_layout_create_info)
+                .expect("Failed to create rigged pipeline layout")
+        };
 
         Self {
             gpu,
@@ -535,6 +693,12 @@ impl RenderModels {
             materials_buffer,
             materials_buffer_index: HashMap::new(),
             materials_buffer_data: Vec::new(),
+            lights_buffer,
+            joints_buffer,
+            sampler,
+            textures: vec![default_texture],
+            textures_index: HashMap::new(),
+            max_textures: setup.max_textures,
             mesh_registry: HashMap::new(),
             shader_vertex_non_rigged,
             shader_fragment_non_rigged,
@@ -543,15 +707,17 @@ impl RenderModels {
             descriptor_sets,
             pipeline_layout_render_non_rigged,
             pipeline_render_non_rigged: vk::Pipeline::null(),
+            shader_vertex_rigged,
+            pipeline_layout_render_rigged,
+            pipeline_render_rigged: vk::Pipeline::null(),
         }
     }
 
-    pub fn globals_uniform(&self) -> GlobalsUniform {
-        let proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_4, 800.0 / 600.0, 1.0, 10.0);
-        let view = Mat4::look_at_rh(Vec3::new(1.5f32, -5.0, 3.0), Vec3::ZERO, Vec3::Z);
+    pub fn globals_uniform(&self, frame: &Frame, camera: &Camera) -> GlobalsUniform {
+        let proj = camera.proj(frame.resolution.width, frame.resolution.height);
         GlobalsUniform {
             proj: proj.to_cols_array_2d(),
-            view: view.to_cols_array_2d(),
+            view: camera.view.to_cols_array_2d(),
         }
     }
 
@@ -562,27 +728,44 @@ impl RenderModels {
         self.signal_semaphore
     }
 
-    fn update_buffers(&mut self, assets: &Assets, world: &World) -> DrawCount {
+    fn update_buffers(
+        &mut self,
+        frame: &Frame,
+        assets: &Assets,
+        camera: &Camera,
+        world: &World,
+    ) -> (DrawCount, DrawOffset) {
         self.instances_skin_mesh_indexed.clear();
         self.instances_only_mesh_indexed.clear();
         self.instances_skin_mesh.clear();
         self.instances_only_mesh.clear();
         // self.materials_buffer_data.clear();
 
-        let globals_uniform = [self.globals_uniform()];
+        let globals_uniform = [self.globals_uniform(frame, camera)];
 
         unsafe {
             self.globals_buffer
                 .map_and_write_to_device_memory(&self.gpu, 0, &globals_uniform);
         }
 
-        for (entity_id, mesh_id, material_id, _armature_id, transform) in world.query::<(
-            &Id<Entity>,
-            &Id<Mesh>,
-            &Id<Material>,
-            &Id<Armature>,
-            &Transform,
-        )>() {
+        let mut joints_buffer_data: Vec<[[f32; 4]; 4]> = Vec::new();
+        let mut joints_buffer_index: HashMap<Id<Armature>, u32> = HashMap::new();
+        let joints_capacity =
+            (self.joints_buffer.size / std::mem::size_of::<[[f32; 4]; 4]>() as u64) as usize;
+        let poses = world
+            .query::<(&Id<Entity>, &Pose)>()
+            .collect::<HashMap<_, _>>();
+
+        for (entity_id, mesh_id, material_id, armature_id, transform, global_transform) in world
+            .query::<(
+                &Id<Entity>,
+                &Id<Mesh>,
+                &Id<Material>,
+                &Id<Armature>,
+                &Transform,
+                Option<&GlobalTransform>,
+            )>()
+        {
             log::debug!("Update buffers: {:?}", entity_id);
             let material_index = self.register_material(*material_id, assets);
             if material_index.is_none() {
@@ -592,6 +775,37 @@ impl RenderModels {
             log::debug!("material index: {} ({:?})", material_index, material_id);
 
             if let Some(mesh_layout) = self.register_mesh(*mesh_id, assets) {
+                let joints_offset = if mesh_layout.has_skin {
+                    let pose = poses
+                        .get(entity_id)
+                        .filter(|pose| !pose.joint_matrices.is_empty());
+                    let joints_offset = match pose {
+                        Some(pose) => Self::push_joint_matrices(
+                            &mut joints_buffer_data,
+                            joints_capacity,
+                            &pose.joint_matrices,
+                        ),
+                        None => joints_buffer_index.get(armature_id).copied().or_else(|| {
+                            let armature = assets.get(*armature_id)?;
+                            let joints_offset = Self::push_joint_matrices(
+                                &mut joints_buffer_data,
+                                joints_capacity,
+                                &armature.bind_pose(),
+                            )?;
+                            joints_buffer_index.insert(*armature_id, joints_offset);
+                            Some(joints_offset)
+                        }),
+                    };
+                    match joints_offset {
+                        Some(joints_offset) => joints_offset,
+                        None => {
+                            log::debug!("armature is not ready: {:?}", armature_id);
+                            continue;
+                        }
+                    }
+                } else {
+                    0
+                };
                 let instances = if mesh_layout.has_skin {
                     if mesh_layout.indices.is_some() {
                         &mut self.instances_skin_mesh_indexed
@@ -607,13 +821,27 @@ impl RenderModels {
                     .entry(*mesh_id)
                     .or_insert_with(|| Vec::with_capacity(1))
                     .push(InstanceUniform {
-                        transform: transform.matrix().to_cols_array_2d(),
+                        transform: global_transform
+                            .map(|global_transform| global_transform.matrix)
+                            .unwrap_or_else(|| transform.matrix())
+                            .to_cols_array_2d(),
                         material_index,
+                        joints_offset,
                         _padding: Default::default(),
                     });
             }
         }
 
+        if !joints_buffer_data.is_empty() {
+            unsafe {
+                self.joints_buffer.map_and_write_to_device_memory(
+                    &self.gpu,
+                    0,
+                    joints_buffer_data.as_slice(),
+                );
+            }
+        }
+
         unsafe {
             self.materials_buffer.map_and_write_to_device_memory(
                 &self.gpu,
@@ -622,14 +850,18 @@ impl RenderModels {
             );
         };
 
+        self.update_lights_buffer(world);
+
         let mut instances_total = 0;
         let mut instance_buffer_offset: u64 = 0;
         let mut indirect_buffer_offset: u64 = 0;
+        let mut draw_offset = DrawOffset::default();
 
         // No indices, no skin
         let only_mesh_draws_count = if !self.instances_only_mesh.is_empty() {
             indirect_buffer_offset +=
                 indirect_buffer_offset % std::mem::size_of::<vk::DrawIndirectCommand>() as u64;
+            draw_offset.only_mesh = indirect_buffer_offset;
             let instances_count = self
                 .instances_only_mesh
                 .values()
@@ -689,6 +921,7 @@ impl RenderModels {
         let only_mesh_indexed_draws_count = if !self.instances_only_mesh_indexed.is_empty() {
             indirect_buffer_offset += indirect_buffer_offset
                 % std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u64;
+            draw_offset.only_mesh_indexed = indirect_buffer_offset;
             let instances_count = self
                 .instances_only_mesh_indexed
                 .values()
@@ -718,12 +951,12 @@ impl RenderModels {
             instances_total += instances_count;
 
             unsafe {
-                self.indirect_buffer.map_and_write_to_device_memory(
+                indirect_buffer_offset += self.indirect_buffer.map_and_write_to_device_memory(
                     &self.gpu,
                     indirect_buffer_offset,
                     indirect_buffer_data.as_slice(),
                 );
-                self.instance_buffer.map_and_write_to_device_memory(
+                instance_buffer_offset += self.instance_buffer.map_and_write_to_device_memory(
                     &self.gpu,
                     instance_buffer_offset,
                     instance_buffer_data.as_slice(),
@@ -738,6 +971,7 @@ impl RenderModels {
         let skin_mesh_draws_count = if !self.instances_skin_mesh.is_empty() {
             indirect_buffer_offset +=
                 indirect_buffer_offset % std::mem::size_of::<vk::DrawIndirectCommand>() as u64;
+            draw_offset.skin_mesh = indirect_buffer_offset;
             let instances_count = self
                 .instances_skin_mesh
                 .values()
@@ -786,6 +1020,7 @@ impl RenderModels {
         let skin_mesh_indexed_draws_count = if !self.instances_skin_mesh_indexed.is_empty() {
             indirect_buffer_offset += indirect_buffer_offset
                 % std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u64;
+            draw_offset.skin_mesh_indexed = indirect_buffer_offset;
             let instances_count = self
                 .instances_skin_mesh_indexed
                 .values()
@@ -831,12 +1066,14 @@ impl RenderModels {
             0
         };
 
-        DrawCount {
+        let draw_count = DrawCount {
             only_mesh: only_mesh_draws_count as u32,
             only_mesh_indexed: only_mesh_indexed_draws_count as u32,
             skin_mesh: skin_mesh_draws_count as u32,
             skin_mesh_indexed: skin_mesh_indexed_draws_count as u32,
-        }
+        };
+
+        (draw_count, draw_offset)
     }
 
     /*
@@ -901,10 +1138,6 @@ impl RenderModels {
         }
         // try to get mesh to store it in buffer
         if let Some(mesh) = assets.get(mesh_id) {
-            if mesh.indices::<u8>().is_some() {
-                panic!("Index buffer is not implemented yet");
-            }
-
             let vertex_data_and_skin_info = mesh
                 .buffer::<VertexBufferSkinMeshLayout>()
                 .map(|vertex_data| {
@@ -943,9 +1176,8 @@ impl RenderModels {
                     },
                     indices: index_data.map(|data| LayoutInBuffer {
                         offset: self.index_buffer_usage,
-                        size: (data.len() * std::mem::size_of_val(data)) as u64,
-                        base: (self.index_buffer_usage / (std::mem::size_of_val(data) as u64))
-                            as u32,
+                        size: std::mem::size_of_val(data) as u64,
+                        base: (self.index_buffer_usage / std::mem::size_of::<u32>() as u64) as u32,
                         count: data.len() as u32,
                     }),
                     has_skin,
@@ -997,22 +1229,281 @@ impl RenderModels {
         None
     }
 
+    fn push_joint_matrices(
+        joints_buffer_data: &mut Vec<[[f32; 4]; 4]>,
+        joints_capacity: usize,
+        joint_matrices: &[Mat4],
+    ) -> Option<u32> {
+        if joints_buffer_data.len() + joint_matrices.len() > joints_capacity {
+            log::error!("Joints buffer overflow, increase `max_joints`");
+            return None;
+        }
+        let joints_offset = joints_buffer_data.len() as u32;
+        joints_buffer_data.extend(joint_matrices.iter().map(|mx| mx.to_cols_array_2d()));
+        Some(joints_offset)
+    }
+
+    fn update_lights_buffer(&mut self, world: &World) {
+        let header_size = std::mem::size_of::<LightsHeader>() as u64;
+        let capacity = ((self.lights_buffer.size - header_size)
+            / std::mem::size_of::<LightUniform>() as u64) as usize;
+
+        let lights = world
+            .query::<(&Light,)>()
+            .filter(|(light,)| light.enabled)
+            .map(|(light,)| light.to_uniform())
+            .collect::<Vec<_>>();
+
+        if lights.len() > capacity {
+            log::warn!(
+                "lights: {} lights in the world, but only {} fit the buffer",
+                lights.len(),
+                capacity
+            );
+        }
+
+        let lights = &lights[0..lights.len().min(capacity)];
+        let header = [LightsHeader {
+            count: lights.len() as u32,
+            _padding: Default::default(),
+        }];
+
+        unsafe {
+            self.lights_buffer
+                .map_and_write_to_device_memory(&self.gpu, 0, &header);
+            if !lights.is_empty() {
+                self.lights_buffer
+                    .map_and_write_to_device_memory(&self.gpu, header_size, lights);
+            }
+        }
+    }
+
     fn register_material(&mut self, material_id: Id<Material>, assets: &Assets) -> Option<u32> {
-        assets.get(material_id).and_then(|material| {
-            self.materials_buffer_index
-                .get(&material_id)
-                .cloned()
-                .or_else(|| {
-                    let index = self.materials_buffer_index.len() as u32;
-                    self.materials_buffer_data.push(MaterialUniform::default());
-                    self.materials_buffer_index.insert(material_id, index);
-                    Some(index)
-                })
-                .map(|material_index| {
-                    self.materials_buffer_data[material_index as usize] = material.into();
-                    material_index
-                })
-        })
+        let material = assets.get(material_id)?;
+        let material_index = match self.materials_buffer_index.get(&material_id) {
+            Some(&index) => index,
+            None => {
+                let index = self.materials_buffer_index.len() as u32;
+                self.materials_buffer_data.push(MaterialUniform::default());
+                self.materials_buffer_index.insert(material_id, index);
+                index
+            }
+        };
+
+        let unorm = vk::Format::R8G8B8A8_UNORM;
+        let mut material_uniform = MaterialUniform::from(material);
+        material_uniform.maps_1 = [
+            self.register_texture(material.occlusion_map, unorm, assets),
+            self.register_texture(material.metallic_map, unorm, assets),
+            self.register_texture(material.normal_map, unorm, assets),
+            self.register_texture(material.roughness_map, unorm, assets),
+        ];
+        material_uniform.maps_2[0] =
+            self.register_texture(material.albedo_map, vk::Format::R8G8B8A8_SRGB, assets);
+
+        self.materials_buffer_data[material_index as usize] = material_uniform;
+        Some(material_index)
+    }
+
+    /// Returns index of the texture in descriptors array, uploads the image if necessary
+    ///
+    /// Index 0 is a default white texture
+    fn register_texture(
+        &mut self,
+        image_id: Id<Image>,
+        format: vk::Format,
+        assets: &Assets,
+    ) -> u32 {
+        if image_id.is_null() {
+            return 0;
+        }
+        if let Some(&index) = self.textures_index.get(&(image_id, format)) {
+            return index;
+        }
+        let image = match assets.get(image_id) {
+            Some(image) => image,
+            None => return 0,
+        };
+
+        let index = self.textures.len() as u32;
+        if index >= self.max_textures {
+            log::warn!(
+                "Texture `{}` does not fit into descriptors array of {} textures",
+                image.name(),
+                self.max_textures
+            );
+            return 0;
+        }
+
+        let resolution = *image.resolution();
+        let size = (resolution.width as usize)
+            .checked_mul(resolution.height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if size != Some(image.data().len()) {
+            log::error!("Texture `{}` is not in RGBA8 format", image.name());
+            return 0;
+        }
+
+        let texture = unsafe {
+            Self::upload_texture(
+                &self.gpu,
+                self.command_buffer_setup,
+                self.command_buffer_setup_reuse_fence,
+                resolution,
+                format,
+                image.data(),
+            )
+        };
+
+        let texture = match texture {
+            Ok(texture) => texture,
+            Err(e) => {
+                log::error!("Could not upload texture `{}`: {:?}", image.name(), e);
+                return 0;
+            }
+        };
+
+        let tex_descriptor = [vk::DescriptorImageInfo {
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            image_view: texture.image_view,
+            sampler: self.sampler,
+        }];
+
+        let write_desc_sets = [vk::WriteDescriptorSet::default()
+            .dst_binding(4)
+            .dst_array_element(index)
+            .dst_set(self.descriptor_sets[0])
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&tex_descriptor)];
+
+        unsafe {
+            // descriptor set must not be in use by the previous frame
+            self.gpu
+                .wait_for_fences(&[self.command_buffer_draw_reuse_fence], true, u64::MAX)
+                .expect("Failed to wait for draw buffer fences");
+            self.gpu.update_descriptor_sets(&write_desc_sets, &[]);
+        }
+
+        log::debug!("texture `{}` registered at {}", image.name(), index);
+        self.textures.push(texture);
+        self.textures_index.insert((image_id, format), index);
+        index
+    }
+
+    /// Creates a texture and copies data into it using the setup command buffer
+    unsafe fn upload_texture(
+        gpu: &Gpu,
+        command_buffer: vk::CommandBuffer,
+        reuse_fence: vk::Fence,
+        resolution: Extent2D,
+        format: vk::Format,
+        data: &[u8],
+    ) -> Result<Texture, vk::Result> {
+        let staging_buffer_create_info = vk::BufferCreateInfo {
+            size: data.len() as u64,
+            usage: vk::BufferUsageFlags::TRANSFER_SRC,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+        let staging_buffer = Buffer::create_and_allocate(gpu, &staging_buffer_create_info)?;
+        staging_buffer.map_and_write_to_device_memory(gpu, 0, data);
+
+        let texture = Texture::create_and_allocate(
+            gpu,
+            resolution,
+            format,
+            vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+        )?;
+
+        gpu.wait_for_fences(&[reuse_fence], true, u64::MAX)
+            .expect("Wait for fence failed.");
+        gpu.reset_fences(&[reuse_fence])
+            .expect("Reset fences failed.");
+        gpu.reset_command_buffer(
+            command_buffer,
+            vk::CommandBufferResetFlags::RELEASE_RESOURCES,
+        )
+        .expect("Reset command buffer failed.");
+
+        let command_buffer_begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        gpu.begin_command_buffer(command_buffer, &command_buffer_begin_info)
+            .expect("Begin commandbuffer");
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1)
+            .level_count(1);
+
+        let transfer_barrier = vk::ImageMemoryBarrier::default()
+            .image(texture.image)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .subresource_range(subresource_range);
+
+        gpu.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[transfer_barrier],
+        );
+
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .layer_count(1),
+            )
+            .image_extent(vk::Extent3D {
+                width: resolution.width,
+                height: resolution.height,
+                depth: 1,
+            });
+
+        gpu.cmd_copy_buffer_to_image(
+            command_buffer,
+            staging_buffer.handle,
+            texture.image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[region],
+        );
+
+        let read_barrier = vk::ImageMemoryBarrier::default()
+            .image(texture.image)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .subresource_range(subresource_range);
+
+        gpu.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[read_barrier],
+        );
+
+        gpu.end_command_buffer(command_buffer)
+            .expect("End commandbuffer");
+
+        let command_buffers = [command_buffer];
+        let submits = [vk::SubmitInfo::default().command_buffers(&command_buffers)];
+
+        gpu.submit_queue(&submits, reuse_fence)
+            .expect("queue submit failed.");
+        gpu.wait_for_fences(&[reuse_fence], true, u64::MAX)
+            .expect("Wait for fence failed.");
+
+        staging_buffer.free_memory_and_destroy(gpu);
+
+        Ok(texture)
     }
 
     /// Returns Buffer, binded memory and allocated size
@@ -1084,7 +1575,8 @@ impl RenderModels {
         let resolution = display.surface_resolution();
         self.framebuffers = display
             .swapchain_image_views()
-            .map(|&present_image_view| {
+            .into_iter()
+            .map(|present_image_view| {
                 let framebuffer_attachments = [present_image_view, display.depth_image_view()];
                 let frame_buffer_create_info = vk::FramebufferCreateInfo::default()
                     .render_pass(render_pass)
@@ -1106,9 +1598,12 @@ impl RenderModels {
         }
     }
 
-    unsafe fn create_graphics_pipelines(&self, surface_resolution: Extent2D) -> vk::Pipeline {
-        let shader_entry_point = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
-        let shader_stages = [
+    unsafe fn create_graphics_pipelines(
+        &self,
+        surface_resolution: Extent2D,
+    ) -> (vk::Pipeline, vk::Pipeline) {
+        let shader_entry_point = c"main";
+        let shader_stages_non_rigged = [
             vk::PipelineShaderStageCreateInfo {
                 module: self.shader_vertex_non_rigged,
                 p_name: shader_entry_point.as_ptr(),
@@ -1123,14 +1618,63 @@ impl RenderModels {
                 ..Default::default()
             },
         ];
+        let shader_stages_rigged = [
+            vk::PipelineShaderStageCreateInfo {
+                module: self.shader_vertex_rigged,
+                p_name: shader_entry_point.as_ptr(),
+                stage: vk::ShaderStageFlags::VERTEX,
+                ..Default::default()
+            },
+            vk::PipelineShaderStageCreateInfo {
+                module: self.shader_fragment_non_rigged,
+                p_name: shader_entry_point.as_ptr(),
+                stage: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+        ];
 
-        // vertex binding
-        let vertex_input_binding_descriptions = [vk::VertexInputBindingDescription {
+        // vertex binding (non-rigged)
+        let vertex_input_binding_descriptions_non_rigged = [vk::VertexInputBindingDescription {
             binding: 0,
             stride: std::mem::size_of::<VertexBufferOnlyMeshLayout>() as u32,
             input_rate: vk::VertexInputRate::VERTEX,
         }];
-        let vertex_input_attribute_descriptions = [
+        let vertex_input_attribute_descriptions_non_rigged = [
+            // position
+            vk::VertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: 0,
+            },
+            // normal
+            vk::VertexInputAttributeDescription {
+                location: 1,
+                binding: 0,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: std::mem::size_of::<VertexPosition>() as u32,
+            },
+            // texture
+            vk::VertexInputAttributeDescription {
+                location: 2,
+                binding: 0,
+                format: vk::Format::R32G32_SFLOAT,
+                offset: std::mem::size_of::<VertexPosition>() as u32
+                    + std::mem::size_of::<VertexNormal>() as u32,
+            },
+        ];
+
+        let vertex_input_state_info_non_rigged = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_attribute_descriptions(&vertex_input_attribute_descriptions_non_rigged)
+            .vertex_binding_descriptions(&vertex_input_binding_descriptions_non_rigged);
+
+        // vertex binding (rigged)
+        let vertex_input_binding_descriptions_rigged = [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: VertexBufferSkinMeshLayout::vertex_size() as u32,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }];
+        let vertex_input_attribute_descriptions_rigged = [
             // position
             vk::VertexInputAttributeDescription {
                 location: 0,
@@ -1153,11 +1697,30 @@ impl RenderModels {
                 offset: std::mem::size_of::<VertexPosition>() as u32
                     + std::mem::size_of::<VertexNormal>() as u32,
             },
+            // weights
+            vk::VertexInputAttributeDescription {
+                location: 3,
+                binding: 0,
+                format: vk::Format::R32G32B32A32_SFLOAT,
+                offset: std::mem::size_of::<VertexPosition>() as u32
+                    + std::mem::size_of::<VertexNormal>() as u32
+                    + std::mem::size_of::<VertexTexture>() as u32,
+            },
+            // joints
+            vk::VertexInputAttributeDescription {
+                location: 4,
+                binding: 0,
+                format: vk::Format::R16G16B16A16_UINT,
+                offset: std::mem::size_of::<VertexPosition>() as u32
+                    + std::mem::size_of::<VertexNormal>() as u32
+                    + std::mem::size_of::<VertexTexture>() as u32
+                    + std::mem::size_of::<VertexWeights>() as u32,
+            },
         ];
+        let vertex_input_state_info_rigged = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_attribute_descriptions(&vertex_input_attribute_descriptions_rigged)
+            .vertex_binding_descriptions(&vertex_input_binding_descriptions_rigged);
 
-        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
-            .vertex_attribute_descriptions(&vertex_input_attribute_descriptions)
-            .vertex_binding_descriptions(&vertex_input_binding_descriptions);
         let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
             topology: vk::PrimitiveTopology::TRIANGLE_LIST,
             ..Default::default()
@@ -1223,9 +1786,9 @@ impl RenderModels {
         let dynamic_state_info =
             vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_state);
 
-        let graphic_pipeline_info = vk::GraphicsPipelineCreateInfo::default()
-            .stages(&shader_stages)
-            .vertex_input_state(&vertex_input_state_info)
+        let graphic_pipeline_info_non_rigged = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&shader_stages_non_rigged)
+            .vertex_input_state(&vertex_input_state_info_non_rigged)
             .input_assembly_state(&vertex_input_assembly_state_info)
             .viewport_state(&viewport_state_info)
             .rasterization_state(&rasterization_info)
@@ -1236,19 +1799,44 @@ impl RenderModels {
             .layout(self.pipeline_layout_render_non_rigged)
             .render_pass(self.render_pass);
 
+        let graphic_pipeline_info_rigged = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&shader_stages_rigged)
+            .vertex_input_state(&vertex_input_state_info_rigged)
+            .input_assembly_state(&vertex_input_assembly_state_info)
+            .viewport_state(&viewport_state_info)
+            .rasterization_state(&rasterization_info)
+            .multisample_state(&multisample_state_info)
+            .depth_stencil_state(&depth_state_info)
+            .color_blend_state(&color_blend_state)
+            .dynamic_state(&dynamic_state_info)
+            .layout(self.pipeline_layout_render_rigged)
+            .render_pass(self.render_pass);
+
         let graphics_pipelines = self
             .gpu
-            .create_graphics_pipelines(vk::PipelineCache::null(), &[graphic_pipeline_info])
+            .create_graphics_pipelines(
+                vk::PipelineCache::null(),
+                &[
+                    graphic_pipeline_info_non_rigged,
+                    graphic_pipeline_info_rigged,
+                ],
+            )
             .expect("Failed to create graphics pipelines");
 
-        graphics_pipelines[0]
+        (graphics_pipelines[0], graphics_pipelines[1])
     }
 
     unsafe fn destroy_graphics_pipelines(&self) {
         self.gpu.destroy_pipeline(self.pipeline_render_non_rigged);
+        self.gpu.destroy_pipeline(self.pipeline_render_rigged);
     }
 
-    unsafe fn execute_render_pass(&self, frame: &Frame, draw_count: DrawCount) {
+    unsafe fn execute_render_pass(
+        &self,
+        frame: &Frame,
+        draw_count: DrawCount,
+        draw_offset: DrawOffset,
+    ) {
         self.gpu
             .wait_for_fences(&[self.command_buffer_draw_reuse_fence], true, u64::MAX)
             .expect("Failed to wait for draw buffer fences");
@@ -1343,57 +1931,79 @@ impl RenderModels {
         );
 
         if draw_count.only_mesh != 0 {
-            let offset = 0;
-            let padding = 0;
             log::debug!(
                 "cmd_draw_indirect(offset: {}, draw_count: {}, stride: {})",
-                offset + padding,
+                draw_offset.only_mesh,
                 draw_count.only_mesh,
                 std::mem::size_of::<vk::DrawIndirectCommand>() as u32
             );
             self.gpu.cmd_draw_indirect(
                 self.command_buffer_draw,
                 self.indirect_buffer.handle,
-                offset + padding,
+                draw_offset.only_mesh,
                 draw_count.only_mesh,
                 std::mem::size_of::<vk::DrawIndirectCommand>() as u32,
             );
         }
 
-        if draw_count.only_mesh_indexed != 0 {
-            let offset =
-                draw_count.only_mesh as u64 * std::mem::size_of::<vk::DrawIndirectCommand>() as u64;
-            let padding = offset % std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u64;
-
+        if draw_count.only_mesh_indexed != 0 || draw_count.skin_mesh_indexed != 0 {
             self.gpu.cmd_bind_index_buffer(
                 self.command_buffer_draw,
                 self.index_buffer.handle,
                 0,
                 vk::IndexType::UINT32,
             );
+        }
 
+        if draw_count.only_mesh_indexed != 0 {
             self.gpu.cmd_draw_indexed_indirect(
                 self.command_buffer_draw,
                 self.indirect_buffer.handle,
-                offset + padding,
-                draw_count.only_mesh,
+                draw_offset.only_mesh_indexed,
+                draw_count.only_mesh_indexed,
                 std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u32,
             );
         }
 
-        log::warn!("Ignored {} skin mesh draws", draw_count.skin_mesh);
-        log::warn!(
-            "Ignored {} skin mesh indexed draws",
-            draw_count.skin_mesh_indexed
-        );
+        if draw_count.skin_mesh != 0 || draw_count.skin_mesh_indexed != 0 {
+            self.gpu.cmd_bind_pipeline(
+                self.command_buffer_draw,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_render_rigged,
+            );
+            self.gpu.cmd_bind_vertex_buffers(
+                self.command_buffer_draw,
+                0,
+                &[self.vertex_buffer_skin_mesh.handle],
+                &[0],
+            );
+        }
+
+        if draw_count.skin_mesh != 0 {
+            self.gpu.cmd_draw_indirect(
+                self.command_buffer_draw,
+                self.indirect_buffer.handle,
+                draw_offset.skin_mesh,
+                draw_count.skin_mesh,
+                std::mem::size_of::<vk::DrawIndirectCommand>() as u32,
+            );
+        }
+
+        if draw_count.skin_mesh_indexed != 0 {
+            self.gpu.cmd_draw_indexed_indirect(
+                self.command_buffer_draw,
+                self.indirect_buffer.handle,
+                draw_offset.skin_mesh_indexed,
+                draw_count.skin_mesh_indexed,
+                std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u32,
+            );
+        }
 
         self.gpu.cmd_end_render_pass(self.command_buffer_draw);
 
         self.gpu
             .end_command_buffer(self.command_buffer_draw)
             .expect("End commandbuffer");
-
-        // TODO: render skin meshes
     }
 
     unsafe fn setup_depth_image(&self, display: &Display) {
@@ -1507,6 +2117,9 @@ pub struct RenderModelsSetup {
     indirect_buffer_size: u64,
     instance_buffer_size: u64,
     materials_buffer_size: u64,
+    lights_buffer_size: u64,
+    joints_buffer_size: u64,
+    max_textures: u32,
 }
 
 impl Default for RenderModelsSetup {
@@ -1521,6 +2134,10 @@ impl Default for RenderModelsSetup {
                 * std::mem::size_of::<vk::DrawIndexedIndirectCommand>() as u64,
             instance_buffer_size: 1000 * std::mem::size_of::<InstanceUniform>() as u64,
             materials_buffer_size: 1000 * std::mem::size_of::<MaterialUniform>() as u64,
+            lights_buffer_size: std::mem::size_of::<LightsHeader>() as u64
+                + 128 * std::mem::size_of::<LightUniform>() as u64,
+            joints_buffer_size: 4096 * std::mem::size_of::<[[f32; 4]; 4]>() as u64,
+            max_textures: 128,
         }
     }
 }
@@ -1536,6 +2153,22 @@ impl RenderModelsSetup {
         self
     }
 
+    pub fn max_lights(mut self, max_lights: u64) -> Self {
+        self.lights_buffer_size = std::mem::size_of::<LightsHeader>() as u64
+            + max_lights * std::mem::size_of::<LightUniform>() as u64;
+        self
+    }
+
+    pub fn max_joints(mut self, max_joints: u64) -> Self {
+        self.joints_buffer_size = max_joints * std::mem::size_of::<[[f32; 4]; 4]>() as u64;
+        self
+    }
+
+    pub fn max_textures(mut self, max_textures: u32) -> Self {
+        self.max_textures = max_textures;
+        self
+    }
+
     pub fn create(self, display: &mut Display) -> RenderModels {
         RenderModels::new(display, self)
     }
@@ -1557,6 +2190,17 @@ pub struct InstanceUniform {
     pub transform: [[f32; 4]; 4],
     /// material index in buffer
     pub material_index: u32,
+    /// Offset of the first skinning matrix in the joints buffer
+    pub joints_offset: u32,
+    /// padding
+    pub _padding: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Debug, Copy, Default)]
+pub struct LightsHeader {
+    /// Number of lights in the buffer
+    pub count: u32,
     /// padding
     pub _padding: [u32; 3],
 }
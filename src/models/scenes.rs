@@ -0,0 +1,150 @@
+//! Scene asset, a hierarchy of nodes to be spawned as entities
+use super::{Armature, Children, GlobalTransform, Material, Mesh, Parent, Transform};
+use crate::loaders::Asset;
+use crate::utils::Id;
+use crate::world::{Entity, World};
+
+/// Node of the [`Scene`]
+#[derive(Debug, Default, Clone)]
+pub struct SceneNode {
+    /// Name of the node
+    pub name: Option<String>,
+    /// Index of the parent node
+    pub parent: Option<usize>,
+    /// Transformation relative to the parent node
+    pub transform: Transform,
+    /// Mesh of the node, nodes without mesh are not rendered
+    pub mesh: Option<Id<Mesh>>,
+    /// Material of the mesh
+    pub material: Id<Material>,
+    /// Armature of the mesh
+    pub armature: Id<Armature>,
+}
+
+/// Hierarchy of nodes, e.g. imported from a glTF file
+#[derive(Debug, Default, Clone)]
+pub struct Scene {
+    /// Name of the scene
+    pub name: String,
+    /// Nodes of the scene, parents always precede their children
+    pub nodes: Vec<SceneNode>,
+}
+
+impl Scene {
+    /// Constructs an empty scene
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Adds the node to the scene and returns its index
+    pub fn add(&mut self, node: SceneNode) -> usize {
+        let index = self.nodes.len();
+        assert!(
+            node.parent.map(|parent| parent < index).unwrap_or(true),
+            "Parent node must be added before its children"
+        );
+        self.nodes.push(node);
+        index
+    }
+
+    /// Spawns nodes as entities, attached to a new root entity with the `transform`
+    ///
+    /// Returns ID of the root entity
+    pub fn spawn(&self, world: &mut World, transform: Transform) -> Id<Entity> {
+        let root = world
+            .spawn([(transform, GlobalTransform::default())])
+            .next()
+            .expect("Root entity to be spawned");
+
+        let mut ids = Vec::with_capacity(self.nodes.len());
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        let mut root_children = Vec::new();
+        for node in self.nodes.iter() {
+            let parent = node.parent.map(|parent| ids[parent]).unwrap_or(root);
+            let mut entity =
+                Entity::new((node.transform, GlobalTransform::default())).with(Parent::new(parent));
+            if let Some(mesh) = node.mesh {
+                entity = entity.with(mesh).with(node.material).with(node.armature);
+            }
+            let id = world
+                .spawn([entity])
+                .next()
+                .expect("Node entity to be spawned");
+            ids.push(id);
+            match node.parent {
+                Some(parent) => children[parent].push(id),
+                None => root_children.push(id),
+            }
+        }
+
+        world.insert(&root, Children(root_children));
+        for (id, children) in ids.iter().zip(children) {
+            if !children.is_empty() {
+                world.insert(id, Children(children));
+            }
+        }
+        root
+    }
+}
+
+impl Asset for Scene {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scene, SceneNode};
+    use crate::loaders::Assets;
+    use crate::math::Vec3;
+    use crate::models::{propagate, Children, GlobalTransform, Mesh, Parent, Transform};
+    use crate::utils::Id;
+    use crate::world::World;
+
+    #[test]
+    fn scene_is_spawned_as_tree() {
+        let mut scene = Scene::new("tank");
+        let hull = scene.add(SceneNode {
+            name: Some(String::from("hull")),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
+            mesh: Some(Id::new()),
+            ..Default::default()
+        });
+        scene.add(SceneNode {
+            name: Some(String::from("turret")),
+            parent: Some(hull),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 2.0)),
+            ..Default::default()
+        });
+
+        let mut world = World::new();
+        let root = scene.spawn(
+            &mut world,
+            Transform::from_translation(Vec3::new(5.0, 0.0, 0.0)),
+        );
+        propagate(&world, &Assets::default());
+
+        let hull = world.component_mut::<Children>(&root).unwrap().0.clone();
+        assert_eq!(hull.len(), 1);
+        let turret = world.component_mut::<Children>(&hull[0]).unwrap().0.clone();
+        assert_eq!(turret.len(), 1);
+        assert_eq!(
+            world
+                .component_mut::<Parent>(&turret[0])
+                .map(|parent| parent.entity),
+            Some(hull[0])
+        );
+        assert!(world.component_mut::<Id<Mesh>>(&hull[0]).is_some());
+        assert!(world.component_mut::<Id<Mesh>>(&turret[0]).is_none());
+        let position = world
+            .component_mut::<GlobalTransform>(&turret[0])
+            .unwrap()
+            .matrix
+            .transform_point3(Vec3::ZERO);
+        assert!(position.abs_diff_eq(Vec3::new(5.0, 0.0, 3.0), 1e-5));
+    }
+}
@@ -1,22 +1,161 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
 #extension GL_ARB_shading_language_420pack : enable
+#extension GL_EXT_nonuniform_qualifier : enable
+
+const float PI = 3.14159265359;
+
+const uint DTX_LIGHT_AMBIENT = 0;
+const uint DTX_LIGHT_POINT = 1;
+const uint DTX_LIGHT_SPOT = 2;
+
+const vec3 DTX_AMBIENT_LIGHT = vec3(0.1, 0.1, 0.1);
+
+struct DtxMaterial {
+    vec4 color;
+    vec4 options;
+    uvec4 maps_1;
+    uvec4 maps_2;
+};
+
+layout(std430, binding = 2) readonly buffer DtxMaterialLayout
+{
+    DtxMaterial dtx_material[];
+};
+
+struct DtxLight {
+    vec4 color;
+    vec4 pos_dir;
+    vec4 stream;
+    vec4 blur;
+    vec4 cut_off;
+    uvec4 options;
+};
+
+layout(std430, binding = 3) readonly buffer DtxLightLayout
+{
+    uvec4 dtx_light_count;
+    DtxLight dtx_light[];
+};
+
+layout(binding = 4) uniform sampler2D dtx_textures[];
 
 layout (location = 0) in vec3 world_position;
 layout (location = 1) in vec3 world_normal;
-layout (location = 2) in vec4 vertex_color;
+layout (location = 2) in vec2 tex_uv;
+layout (location = 3) flat in uint material_index;
+layout (location = 4) in vec3 camera_position;
 layout (location = 0) out vec4 o_frag_color;
 
+vec4 sample_map(uint index) {
+    return texture(dtx_textures[nonuniformEXT(index)], tex_uv);
+}
+
+// Normal mapping without tangents, using screen space derivatives
+vec3 perturb_normal(vec3 normal, uint normal_map) {
+    vec3 tangent_normal = sample_map(normal_map).xyz * 2.0 - 1.0;
+
+    vec3 dp1 = dFdx(world_position);
+    vec3 dp2 = dFdy(world_position);
+    vec2 duv1 = dFdx(tex_uv);
+    vec2 duv2 = dFdy(tex_uv);
+
+    vec3 dp2perp = cross(dp2, normal);
+    vec3 dp1perp = cross(normal, dp1);
+    vec3 tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    float invmax = inversesqrt(max(dot(tangent, tangent), dot(bitangent, bitangent)));
+    mat3 tbn = mat3(tangent * invmax, bitangent * invmax, normal);
+
+    return normalize(tbn * tangent_normal);
+}
+
+float attenuation(DtxLight light, float distance) {
+    float denominator = light.blur.x + light.blur.y * distance + light.blur.z * distance * distance;
+    if (denominator > 0.0) {
+        return 1.0 / denominator;
+    }
+    return 1.0;
+}
+
+float distribution_ggx(vec3 normal, vec3 halfway, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float n_dot_h = max(dot(normal, halfway), 0.0);
+    float denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denominator * denominator);
+}
+
+float geometry_schlick_ggx(float n_dot_v, float roughness) {
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+    return n_dot_v / (n_dot_v * (1.0 - k) + k);
+}
+
+float geometry_smith(vec3 normal, vec3 view, vec3 light_direction, float roughness) {
+    float n_dot_v = max(dot(normal, view), 0.0);
+    float n_dot_l = max(dot(normal, light_direction), 0.0);
+    return geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
 void main() {
-    vec3 ambient_light = vec3(0.5, 0.5, 0.5);
-    vec4 color = vec4(0.0, 0.0, 0.0, 0.0);
-    vec3 light_color = vec3(1.0, 1.0, 1.0);
-    vec3 light_position = vec3(10.0, 10.0, 10.0); 
+    DtxMaterial material = dtx_material[material_index];
+
+    vec4 albedo = material.color * sample_map(material.maps_2.x);
+    float occlusion = material.options.x * sample_map(material.maps_1.x).r;
+    float metallic = material.options.y * sample_map(material.maps_1.y).b;
+    float roughness = material.options.z * sample_map(material.maps_1.w).g;
+    roughness = clamp(roughness, 0.04, 1.0);
 
     vec3 normal = normalize(world_normal);
-    vec3 light_direction = normalize(light_position - world_position);
+    if (material.maps_1.z != 0) {
+        normal = perturb_normal(normal, material.maps_1.z);
+    }
+
+    vec3 view = normalize(camera_position - world_position);
+    vec3 f0 = mix(vec3(0.04), albedo.rgb, metallic);
+    vec3 radiance_sum = vec3(0.0);
+
+    for (uint i = 0; i < dtx_light_count.x; i++) {
+        DtxLight light = dtx_light[i];
+        uint kind = light.options.x;
+        vec3 light_direction;
+        float intensity = 1.0;
+
+        if (kind == DTX_LIGHT_AMBIENT) {
+            light_direction = normalize(-light.pos_dir.xyz);
+        } else {
+            vec3 to_light = light.pos_dir.xyz - world_position;
+            light_direction = normalize(to_light);
+            intensity = attenuation(light, length(to_light));
+
+            if (kind == DTX_LIGHT_SPOT) {
+                float theta = dot(light_direction, normalize(-light.stream.xyz));
+                float epsilon = light.cut_off.x - light.cut_off.y;
+                intensity *= clamp((theta - light.cut_off.y) / max(epsilon, 0.0001), 0.0, 1.0);
+            }
+        }
+
+        vec3 halfway = normalize(view + light_direction);
+        vec3 radiance = light.color.rgb * intensity;
+
+        float ndf = distribution_ggx(normal, halfway, roughness);
+        float geometry = geometry_smith(normal, view, light_direction, roughness);
+        vec3 fresnel = fresnel_schlick(max(dot(halfway, view), 0.0), f0);
+
+        float n_dot_l = max(dot(normal, light_direction), 0.0);
+        vec3 specular = (ndf * geometry * fresnel)
+            / (4.0 * max(dot(normal, view), 0.0) * n_dot_l + 0.0001);
+        vec3 diffuse = (vec3(1.0) - fresnel) * (1.0 - metallic);
+
+        radiance_sum += (diffuse * albedo.rgb / PI + specular) * radiance * n_dot_l;
+    }
 
-    vec3 diffuse = max(dot(normal, light_direction), 0.0) * light_color;
+    vec3 ambient = DTX_AMBIENT_LIGHT * albedo.rgb * occlusion;
 
-    o_frag_color = vec4((ambient_light + diffuse), 1.0) * vertex_color;
+    o_frag_color = vec4(ambient + radiance_sum, albedo.a);
 }
@@ -14,7 +14,8 @@ layout (binding = 0) uniform DtxGlobals {
 struct DtxInstance {
     mat4 transform;
     uint material_index;
-    uint _padding[3]; 
+    uint joints_offset;
+    uint _padding[2];
 };
 
 layout(std430, binding = 1) buffer DtxInstanceLayout
@@ -22,30 +23,23 @@ layout(std430, binding = 1) buffer DtxInstanceLayout
     DtxInstance dtx_instance[];
 };
 
-struct DtxMaterial {
-    vec4 color;
-    vec4 options;
-    uvec4 maps_1;
-    uvec4 maps_2;
-};
-
-layout(std430, binding = 2) buffer DtxMaterialLayout
-{
-    DtxMaterial dtx_material[];
-};
-
 layout (location = 0) out vec3 o_world_position;
 layout (location = 1) out vec3 o_world_normal;
-layout (location = 2) out vec4 o_color;
+layout (location = 2) out vec2 o_texture;
+layout (location = 3) flat out uint o_material_index;
+layout (location = 4) out vec3 o_camera_position;
+
 void main() {
     mat4 model_transform = dtx_instance[gl_InstanceIndex].transform;
-    uint material_index = dtx_instance[gl_InstanceIndex].material_index;
-    vec4 material_color = dtx_material[material_index].color;
     
     mat4 proj_view = dtx_globals.proj * dtx_globals.view;
     o_world_position = vec3(model_transform * vec4(pos, 1.0));
-    o_world_normal = vec3(model_transform * vec4(normal, 1.0));
-    o_color = vec4(material_color);
+    // NOTE: normal matrix keeps normals perpendicular to surfaces under non-uniform scale
+    mat3 normal_transform = transpose(inverse(mat3(model_transform)));
+    o_world_normal = normalize(normal_transform * normal);
+    o_texture = texture;
+    o_material_index = dtx_instance[gl_InstanceIndex].material_index;
+    o_camera_position = inverse(dtx_globals.view)[3].xyz;
 
     gl_Position = proj_view * vec4(o_world_position, 1.0);
 }
@@ -0,0 +1,58 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 normal;
+layout (location = 2) in vec2 texture;
+layout (location = 3) in vec4 weights;
+layout (location = 4) in uvec4 joints;
+
+layout (binding = 0) uniform DtxGlobals {
+    mat4 proj;
+    mat4 view;
+} dtx_globals;
+
+struct DtxInstance {
+    mat4 transform;
+    uint material_index;
+    uint joints_offset;
+    uint _padding[2];
+};
+
+layout(std430, binding = 1) buffer DtxInstanceLayout
+{
+    DtxInstance dtx_instance[];
+};
+
+layout(std430, binding = 5) buffer DtxJointsLayout
+{
+    mat4 dtx_joints[];
+};
+
+layout (location = 0) out vec3 o_world_position;
+layout (location = 1) out vec3 o_world_normal;
+layout (location = 2) out vec2 o_texture;
+layout (location = 3) flat out uint o_material_index;
+layout (location = 4) out vec3 o_camera_position;
+
+void main() {
+    uint joints_offset = dtx_instance[gl_InstanceIndex].joints_offset;
+    mat4 skin_transform =
+        weights.x * dtx_joints[joints_offset + joints.x] +
+        weights.y * dtx_joints[joints_offset + joints.y] +
+        weights.z * dtx_joints[joints_offset + joints.z] +
+        weights.w * dtx_joints[joints_offset + joints.w];
+    mat4 model_transform = dtx_instance[gl_InstanceIndex].transform * skin_transform;
+    
+    mat4 proj_view = dtx_globals.proj * dtx_globals.view;
+    o_world_position = vec3(model_transform * vec4(pos, 1.0));
+    // NOTE: normal matrix keeps normals perpendicular to surfaces under non-uniform scale
+    mat3 normal_transform = transpose(inverse(mat3(model_transform)));
+    o_world_normal = normalize(normal_transform * normal);
+    o_texture = texture;
+    o_material_index = dtx_instance[gl_InstanceIndex].material_index;
+    o_camera_position = inverse(dtx_globals.view)[3].xyz;
+
+    gl_Position = proj_view * vec4(o_world_position, 1.0);
+}
@@ -1,4 +1,6 @@
 //! Transformation structure and builder
+use serde::{Deserialize, Serialize};
+
 use crate::math::{Mat4, Quat, Vec3};
 
 /// Agregator for skin transformations
@@ -51,7 +53,7 @@ impl TransformBuilder {
 }
 
 /// Model transformation structure
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Transform {
     /// Translation vector
     pub translate: Vec3,
@@ -90,11 +90,11 @@ impl TaskManager {
         }
     }
 
-    fn lock_scheduler_tx(&self) -> MutexGuard<mpsc::Sender<scheduler::Message>> {
+    fn lock_scheduler_tx(&self) -> MutexGuard<'_, mpsc::Sender<scheduler::Message>> {
         self.scheduler_tx.lock().expect("Mutex to be locked")
     }
 
-    pub fn scheduler(&self) -> Scheduler {
+    pub fn scheduler(&self) -> Scheduler<'_> {
         Scheduler {
             guard: self.lock_scheduler_tx(),
         }
@@ -984,7 +984,7 @@ impl<T: Context> Any<T> {
 
 impl<T: Context> All<T> {
     /// Returns iterator across outputs
-    pub fn iter(&self) -> AllIter<T> {
+    pub fn iter(&self) -> AllIter<'_, T> {
         AllIter {
             inner: self.data.iter(),
         }
@@ -1051,7 +1051,7 @@ impl<T: Context> Take<All<T>> {
     }
 
     /// Returns draining iterator over the selected outputs
-    pub fn drain(&mut self) -> TakeAllIter<T> {
+    pub fn drain(&mut self) -> TakeAllIter<'_, T> {
         let len = self.selection.data.len();
         TakeAllIter {
             inner: self.selection.data.drain(0..len),
@@ -126,6 +126,18 @@ impl<T> Default for Id<T> {
 unsafe impl<T> Send for Id<T> {}
 unsafe impl<T> Sync for Id<T> {}
 
+impl<T> serde::Serialize for Id<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T> serde::Deserialize<'de> for Id<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        uuid::Uuid::deserialize(deserializer).map(Id::from)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
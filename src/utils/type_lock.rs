@@ -38,7 +38,7 @@ impl TypeLock {
                 Lock::ReadOnly(type_id) => self
                     .data
                     .get(type_id)
-                    .map(|mode| (*mode != LockMode::ReadWrite))
+                    .map(|mode| *mode != LockMode::ReadWrite)
                     .unwrap_or(true),
                 Lock::ReadWrite(type_id) => !self.data.contains_key(type_id),
             };
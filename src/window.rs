@@ -1,17 +1,23 @@
+mod actions;
+mod gamepad;
 mod input;
-// mod map;
+mod map;
+mod replay;
 
 pub mod event;
 
 use std::sync::Arc;
 use std::time;
 
+pub use actions::{Actions, Axis, Binding, Bindings, Control, MapActions, MouseButton};
 pub use event::Event;
-pub use input::ReadInput;
+pub use gamepad::{GamepadDecoder, Gamepads, DEFAULT_DEAD_ZONE};
+pub use input::{Input, ReadInput};
+pub use replay::{InputRecorded, InputSource, RecordInput, RecordedFrame, Recording, ReplayInput};
 use winit::event::StartCause;
 
 use crate::graphics::{self, Display, DisplaySetup, Extent2D};
-use crate::tasks::TaskManager;
+use crate::tasks::{Scheduler, TaskManager};
 use crate::Application;
 
 /// Window resize request context
@@ -53,6 +59,38 @@ impl Instance {
     }
 }
 
+/// Adds [`graphics::CreateFrame`] and tasks, providing [`Input`] from the source
+///
+/// Frames of a replayed recording get the recorded deltas, so the replay does not depend on
+/// timing of the current run.
+pub fn add_input_tasks(
+    scheduler: &Scheduler,
+    input_source: InputSource,
+    create_frame: graphics::CreateFrame,
+) {
+    match input_source {
+        InputSource::Live => {
+            scheduler.add_task(create_frame);
+            scheduler.add_task(ReadInput::new());
+        }
+        InputSource::Record(path) => {
+            scheduler.add_task(create_frame);
+            scheduler.add_task(ReadInput::new());
+            scheduler.add_task(RecordInput::new(&path).unwrap_or_else(|error| {
+                panic!("Could not create input recording {:?}: {}", path, error)
+            }));
+        }
+        InputSource::Replay(path) => {
+            let recording = Recording::load(&path).unwrap_or_else(|error| {
+                panic!("Could not load input recording {:?}: {}", path, error)
+            });
+            let deltas = recording.frames.iter().map(|frame| frame.delta);
+            scheduler.add_task(create_frame.recorded_deltas(deltas));
+            scheduler.add_task(ReplayInput::new(recording));
+        }
+    }
+}
+
 /// Main Loop
 pub struct EventLoop<T: Application> {
     application: Option<T>,
@@ -61,6 +99,7 @@ pub struct EventLoop<T: Application> {
     close_requested: bool,
     frame_duration: std::time::Duration,
     window_instance: Option<Instance>,
+    gamepads: Option<Gamepads>,
     task_manager: TaskManager,
 }
 
@@ -81,6 +120,7 @@ impl<T: Application> EventLoop<T> {
             wait_cancelled: false,
             close_requested: false,
             window_instance: None,
+            gamepads: None,
             task_manager: TaskManager::new::<graphics::FramePresenter>(workers),
         }
     }
@@ -118,6 +158,12 @@ impl<T: Application> winit::application::ApplicationHandler for EventLoop<T> {
 
         let resolution = app.resolution();
 
+        self.gamepads = app.gamepad_dead_zone().map(|dead_zone| {
+            let gamepads = Gamepads::new(dead_zone);
+            gamepads.watch_devices();
+            gamepads
+        });
+
         let window_attributes = winit::window::Window::default_attributes()
             .with_title(app.app_name())
             .with_inner_size(winit::dpi::LogicalSize::new(
@@ -148,30 +194,16 @@ impl<T: Application> winit::application::ApplicationHandler for EventLoop<T> {
         // }
 
         let display_setup = DisplaySetup {
-            window_instance: window_instance.clone(),
+            window_instance: Some(window_instance.clone()),
+            resolution,
             app_name: app.app_name(),
             app_version: app.app_version(),
             debug: app.debug(),
             device_type_request: app.device_type_request(),
         };
 
-        let mut display = Display::new(display_setup);
-        {
-            let scheduler = self.task_manager.scheduler();
-
-            let create_frame_task = graphics::CreateFrame::default()
-                .log_fps_interval(app.log_fps_interval())
-                .fps_request(app.fps_request());
-            scheduler.add_task(create_frame_task);
-
-            let submit_frame_task = graphics::SubmitFrame::default();
-            scheduler.add_task(submit_frame_task);
-
-            app.startup(&scheduler, &mut display);
-
-            // add Display context
-            scheduler.add_context(display);
-        }
+        let display = Display::new(display_setup);
+        crate::setup(app, &self.task_manager, display);
 
         self.window_instance = Some(window_instance);
 
@@ -184,24 +216,14 @@ impl<T: Application> winit::application::ApplicationHandler for EventLoop<T> {
         _window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
-        log::info!("{event:?}");
+        log::debug!("{event:?}");
+        if let Some(input_event) = map::window_event(&event) {
+            self.task_manager.provide(input_event);
+        }
         match event {
             winit::event::WindowEvent::CloseRequested => {
                 self.close_requested = true;
             }
-            winit::event::WindowEvent::KeyboardInput {
-                event:
-                    winit::event::KeyEvent {
-                        logical_key: key,
-                        state: winit::event::ElementState::Pressed,
-                        ..
-                    },
-                ..
-            } => {
-                log::info!("input: {key:?}");
-                // TODO: map to window::Event and provide
-                // self.task_manager.provide(event);
-            }
             winit::event::WindowEvent::RedrawRequested => {
                 if let Some(instance) = self.window_instance.as_ref() {
                     instance.winit_window.pre_present_notify();
@@ -227,6 +249,17 @@ impl<T: Application> winit::application::ApplicationHandler for EventLoop<T> {
         }
     }
 
+    fn device_event(
+        &mut self,
+        _event_loop: &winit::event_loop::ActiveEventLoop,
+        _device_id: winit::event::DeviceId,
+        event: winit::event::DeviceEvent,
+    ) {
+        if let Some(input_event) = map::device_event(&event) {
+            self.task_manager.provide(input_event);
+        }
+    }
+
     fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         log::debug!(
             "about_to_wait(request_redraw: {}, wait_cancelled: {}, close_requested: {})",
@@ -235,6 +268,12 @@ impl<T: Application> winit::application::ApplicationHandler for EventLoop<T> {
             self.close_requested,
         );
 
+        if let Some(gamepads) = self.gamepads.as_ref() {
+            for event in gamepads.poll() {
+                self.task_manager.provide(event);
+            }
+        }
+
         // NOTE: to wait
         // event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait),
 
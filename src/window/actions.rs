@@ -0,0 +1,417 @@
+//! Input actions
+//!
+//! Actions abstract game logic from concrete controls: tasks query "Jump" or "MoveForward"
+//! instead of keys and buttons, while players are free to rebind the controls.
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::event::{Button, GamepadAxis, GamepadButton, KeyCode, Modifiers};
+use super::input::Input;
+use crate::log;
+use crate::tasks::{Any, Ref, Task};
+
+/// Axis of the mouse wheel or movement
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Axis {
+    /// Horizontal axis
+    Horizontal,
+    /// Vertical axis
+    Vertical,
+}
+
+/// Mouse button
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum MouseButton {
+    /// Left mouse button
+    Left,
+    /// Right mouse button
+    Right,
+    /// Middle mouse button
+    Middle,
+    /// Forward mouse button
+    Forward,
+    /// Back mouse button
+    Back,
+    /// Other mouse button represented by numeric code
+    Other(u16),
+}
+
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => Button::MouseLeft,
+            MouseButton::Right => Button::MouseRight,
+            MouseButton::Middle => Button::MouseMiddle,
+            MouseButton::Forward => Button::Forward,
+            MouseButton::Back => Button::Back,
+            MouseButton::Other(code) => Button::MouseOther(code),
+        }
+    }
+}
+
+/// Control, that can be bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Control {
+    /// Keyboard key
+    Key(KeyCode),
+    /// Mouse button
+    Mouse(MouseButton),
+    /// Mouse wheel scroll along the axis in lines, or in pixels for touchpads
+    MouseWheel(Axis),
+    /// Mouse movement along the axis
+    MouseMove(Axis),
+    /// Button of any gamepad
+    GamepadButton(GamepadButton),
+    /// Axis of any gamepad
+    GamepadAxis(GamepadAxis),
+}
+
+impl Control {
+    /// Returns value of the control for the input
+    fn value(&self, input: &Input) -> f32 {
+        match self {
+            Control::Key(key_code) => {
+                let held = input.hold.keys().any(|button| {
+                    matches!(button, Button::Key { key_code: Some(code), .. } if code == key_code)
+                });
+                if held {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Control::Mouse(mouse_button) => {
+                if input.hold.contains_key(&Button::from(*mouse_button)) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Control::MouseWheel(Axis::Horizontal) => {
+                (input.mouse_scroll_delta_lines.horizontal
+                    + input.mouse_scroll_delta_pixels.horizontal) as f32
+            }
+            Control::MouseWheel(Axis::Vertical) => {
+                (input.mouse_scroll_delta_lines.vertical + input.mouse_scroll_delta_pixels.vertical)
+                    as f32
+            }
+            Control::MouseMove(Axis::Horizontal) => input.mouse_move_delta.horizontal as f32,
+            Control::MouseMove(Axis::Vertical) => input.mouse_move_delta.vertical as f32,
+            Control::GamepadButton(gamepad_button) => {
+                let held = input.hold.keys().any(|button| {
+                    matches!(button, Button::Gamepad { button, .. } if button == gamepad_button)
+                });
+                if held {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Control::GamepadAxis(gamepad_axis) => input
+                .gamepad_axes
+                .iter()
+                .filter(|((_, axis), _)| axis == gamepad_axis)
+                .map(|(_, value)| *value)
+                .sum(),
+        }
+    }
+}
+
+/// Binding of a control to an action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    /// Name of the action
+    pub action: String,
+    /// Bound control
+    pub control: Control,
+    /// Modifiers, that must be held to activate the binding
+    pub modifiers: Modifiers,
+    /// Multiplier of the control value, e.g. `-1.0` for the reverse direction of an axis
+    pub scale: f32,
+}
+
+impl Binding {
+    /// Constructs a new binding without modifiers
+    pub fn new(action: impl Into<String>, control: Control) -> Self {
+        Self {
+            action: action.into(),
+            control,
+            modifiers: Modifiers::empty(),
+            scale: 1.0,
+        }
+    }
+
+    /// Sets modifiers of the binding
+    pub fn modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Sets multiplier of the control value
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns value of the binding for the input
+    fn value(&self, input: &Input) -> f32 {
+        if !input.modifiers.contains(self.modifiers) {
+            return 0.0;
+        }
+        self.control.value(input) * self.scale
+    }
+}
+
+/// Sets of bindings, global context of the [`MapActions`] task
+///
+/// Global bindings are always applied, while only one of the named sets can be active, so
+/// tasks of different application states can switch their own controls.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Bindings {
+    /// Bindings applied in any state
+    pub global: Vec<Binding>,
+    /// Named sets of bindings
+    pub sets: HashMap<String, Vec<Binding>>,
+    /// Name of the active set
+    #[serde(skip)]
+    active: Option<String>,
+}
+
+impl Bindings {
+    /// Constructs empty bindings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a global binding
+    pub fn bind(mut self, binding: Binding) -> Self {
+        self.global.push(binding);
+        self
+    }
+
+    /// Adds a binding to the named set
+    pub fn bind_in(mut self, set: impl Into<String>, binding: Binding) -> Self {
+        self.sets.entry(set.into()).or_default().push(binding);
+        self
+    }
+
+    /// Activates the named set, or deactivates all sets if `None`
+    pub fn activate(&mut self, set: Option<&str>) {
+        if let Some(name) = set {
+            if !self.sets.contains_key(name) {
+                log::warn!("Bindings set '{}' does not exist", name);
+            }
+        }
+        self.active = set.map(String::from);
+    }
+
+    /// Returns name of the active set
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Replaces `control` of the action with a new one in global bindings and all sets
+    ///
+    /// Returns number of rebound bindings
+    pub fn rebind(&mut self, action: &str, control: Control, new_control: Control) -> usize {
+        let mut count = 0;
+        for binding in self
+            .global
+            .iter_mut()
+            .chain(self.sets.values_mut().flatten())
+            .filter(|binding| binding.action == action && binding.control == control)
+        {
+            binding.control = new_control;
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns iterator over bindings of the active set and global ones
+    pub fn iter(&self) -> impl Iterator<Item = &Binding> {
+        let active = self
+            .active
+            .as_ref()
+            .and_then(|name| self.sets.get(name))
+            .map(|bindings| bindings.iter())
+            .unwrap_or_default();
+        self.global.iter().chain(active)
+    }
+}
+
+/// Actions of the current frame, output of the [`MapActions`] task
+#[derive(Debug, Default, Clone)]
+pub struct Actions {
+    values: HashMap<String, f32>,
+    started: HashSet<String>,
+    stopped: HashSet<String>,
+}
+
+impl Actions {
+    /// Returns value of the analog action, sum of values of all its bindings
+    pub fn value(&self, action: &str) -> f32 {
+        self.values.get(action).copied().unwrap_or(0.0)
+    }
+
+    /// Returns true if the digital action is active
+    pub fn is_active(&self, action: &str) -> bool {
+        self.values.contains_key(action)
+    }
+
+    /// Returns true if the action became active in this frame
+    pub fn just_started(&self, action: &str) -> bool {
+        self.started.contains(action)
+    }
+
+    /// Returns true if the action became inactive in this frame
+    pub fn just_stopped(&self, action: &str) -> bool {
+        self.stopped.contains(action)
+    }
+
+    /// Returns iterator over active actions and their values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.values
+            .iter()
+            .map(|(action, value)| (action.as_str(), *value))
+    }
+}
+
+/// Task, responsible for mapping of the input into actions
+#[derive(Default)]
+pub struct MapActions {
+    active: HashSet<String>,
+}
+
+impl MapActions {
+    /// Constructs new instance of the task
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the input into actions using the bindings
+    pub fn map(&mut self, input: &Input, bindings: &Bindings) -> Actions {
+        let mut values: HashMap<String, f32> = HashMap::new();
+        for binding in bindings.iter() {
+            let value = binding.value(input);
+            if value != 0.0 {
+                *values.entry(binding.action.clone()).or_default() += value;
+            }
+        }
+        values.retain(|_, value| *value != 0.0);
+
+        let started = values
+            .keys()
+            .filter(|action| !self.active.contains(*action))
+            .cloned()
+            .collect::<HashSet<_>>();
+        let stopped = self
+            .active
+            .iter()
+            .filter(|action| !values.contains_key(*action))
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        self.active = values.keys().cloned().collect();
+
+        Actions {
+            values,
+            started,
+            stopped,
+        }
+    }
+}
+
+impl Task for MapActions {
+    type Context = (Any<Input>, Ref<Bindings>);
+    type Output = Actions;
+
+    fn run(&mut self, (input, bindings): Self::Context) -> Self::Output {
+        self.map(&input, &bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::{Axis, Binding, Bindings, Control, MapActions, MouseButton};
+    use crate::window::event::{Button, KeyCode, Modifiers};
+    use crate::window::Input;
+
+    fn key(key_code: KeyCode) -> Button {
+        Button::Key {
+            key_code: Some(key_code),
+            scan_code: 0,
+        }
+    }
+
+    #[test]
+    fn input_is_mapped_into_actions() {
+        let mut bindings = Bindings::new()
+            .bind(Binding::new("MoveForward", Control::Key(KeyCode::W)))
+            .bind(Binding::new("MoveForward", Control::Key(KeyCode::S)).scale(-1.0))
+            .bind(Binding::new("Save", Control::Key(KeyCode::S)).modifiers(Modifiers::CTRL))
+            .bind_in(
+                "game",
+                Binding::new("Fire", Control::Mouse(MouseButton::Left)),
+            )
+            .bind_in(
+                "game",
+                Binding::new("Zoom", Control::MouseWheel(Axis::Vertical)),
+            );
+        let mut map_actions = MapActions::new();
+
+        let mut input = Input::default();
+        input.hold.insert(key(KeyCode::W), Instant::now());
+        input.hold.insert(Button::MouseLeft, Instant::now());
+        input.mouse_scroll_delta_lines.vertical = 2.0;
+
+        let actions = map_actions.map(&input, &bindings);
+        assert_eq!(actions.value("MoveForward"), 1.0);
+        assert!(actions.just_started("MoveForward"));
+        assert!(!actions.is_active("Fire"));
+        assert!(!actions.is_active("Zoom"));
+
+        bindings.activate(Some("game"));
+        input.hold.insert(key(KeyCode::S), Instant::now());
+        let actions = map_actions.map(&input, &bindings);
+        assert!(!actions.is_active("MoveForward"));
+        assert!(actions.just_stopped("MoveForward"));
+        assert!(!actions.is_active("Save"));
+        assert!(actions.just_started("Fire"));
+        assert_eq!(actions.value("Zoom"), 2.0);
+
+        input.modifiers = Modifiers::CTRL;
+        let actions = map_actions.map(&input, &bindings);
+        assert!(actions.just_started("Save"));
+        assert!(actions.is_active("Fire"));
+        assert!(!actions.just_started("Fire"));
+    }
+
+    #[test]
+    fn bindings_can_be_rebound_and_serialized() {
+        let mut bindings = Bindings::new()
+            .bind(Binding::new("Jump", Control::Key(KeyCode::Space)))
+            .bind_in(
+                "menu",
+                Binding::new("Back", Control::Key(KeyCode::Escape)).modifiers(Modifiers::SHIFT),
+            );
+
+        let rebound = bindings.rebind(
+            "Jump",
+            Control::Key(KeyCode::Space),
+            Control::Mouse(MouseButton::Right),
+        );
+        assert_eq!(rebound, 1);
+
+        let json = serde_json::to_string(&bindings).unwrap();
+        let restored: Bindings = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.global, bindings.global);
+        assert_eq!(restored.sets, bindings.sets);
+        assert_eq!(
+            restored.global[0].control,
+            Control::Mouse(MouseButton::Right)
+        );
+    }
+}
@@ -1,12 +1,13 @@
 //! Input Abstractions
 
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 
 /// Key Scan Code
 pub type ScanCode = u32;
 
 /// Window input event
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Event {
     /// Button press event
     ButtonPress {
@@ -49,10 +50,32 @@ pub enum Event {
         /// Drag'n'drop target
         target: DragAndDrop,
     },
+    /// Gamepad axis change event
+    GamepadAxis {
+        /// Gamepad identifier
+        gamepad: GamepadId,
+        /// Changed axis
+        axis: GamepadAxis,
+        /// Axis value in range -1.0..=1.0 for sticks and 0.0..=1.0 for triggers
+        value: f32,
+    },
+    /// Gamepad connection event
+    GamepadConnected {
+        /// Gamepad identifier
+        gamepad: GamepadId,
+    },
+    /// Gamepad disconnection event
+    GamepadDisconnected {
+        /// Gamepad identifier
+        gamepad: GamepadId,
+    },
 }
 
+/// Gamepad identifier
+pub type GamepadId = u32;
+
 /// Drag and drop event
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DragAndDrop {
     /// File dragged over the window
     FileDragged {
@@ -69,7 +92,7 @@ pub enum DragAndDrop {
 }
 
 /// Button press event
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Button {
     /// Keyboard button
     Key {
@@ -90,10 +113,73 @@ pub enum Button {
     Back,
     /// Other mouse button represented by numeric code
     MouseOther(u16),
+    /// Gamepad button
+    Gamepad {
+        /// Gamepad identifier
+        gamepad: GamepadId,
+        /// Gamepad button
+        button: GamepadButton,
+    },
+}
+
+/// Gamepad button
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    /// Bottom button of the right cluster (A on Xbox controllers)
+    South,
+    /// Right button of the right cluster (B on Xbox controllers)
+    East,
+    /// Left button of the right cluster (X on Xbox controllers)
+    West,
+    /// Top button of the right cluster (Y on Xbox controllers)
+    North,
+    /// Left bumper
+    LeftBumper,
+    /// Right bumper
+    RightBumper,
+    /// Select (back) button
+    Select,
+    /// Start button
+    Start,
+    /// Mode (guide) button
+    Mode,
+    /// Left stick press
+    LeftThumb,
+    /// Right stick press
+    RightThumb,
+    /// D-pad up
+    DPadUp,
+    /// D-pad down
+    DPadDown,
+    /// D-pad left
+    DPadLeft,
+    /// D-pad right
+    DPadRight,
+    /// Other button represented by numeric code
+    Other(u8),
+}
+
+/// Gamepad axis
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    /// Horizontal axis of the left stick
+    LeftStickX,
+    /// Vertical axis of the left stick
+    LeftStickY,
+    /// Horizontal axis of the right stick
+    RightStickX,
+    /// Vertical axis of the right stick
+    RightStickY,
+    /// Left trigger
+    LeftTrigger,
+    /// Right trigger
+    RightTrigger,
+    /// Other axis represented by numeric code
+    Other(u8),
 }
 
 /// Mouse scroll event
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum MouseScroll {
     /// Scroll delta in pixels (usually for touchpads)
     Pixels {
@@ -113,7 +199,7 @@ pub enum MouseScroll {
 
 bitflags! {
     /// State of modifiers
-    #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+    #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
     pub struct Modifiers: u32 {
         /// Shift modifier
         const SHIFT = 0b100;
@@ -160,7 +246,7 @@ impl Modifiers {
 
 /// Keyboard key codes
 #[allow(missing_docs)]
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum KeyCode {
     Key1,
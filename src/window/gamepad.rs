@@ -0,0 +1,446 @@
+//! Gamepad input source
+//!
+//! Gamepads are read through the Linux joystick interface (`/dev/input/js*`). Buttons and axes
+//! are mapped using the layout of the `xpad` driver, that is used by most of XInput compatible
+//! controllers.
+use std::collections::HashMap;
+use std::io::Read;
+#[cfg(target_os = "linux")]
+use std::os::unix::{fs::OpenOptionsExt, io::AsRawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+use super::event::{Button, Event, GamepadAxis, GamepadButton, GamepadId};
+use crate::log;
+
+/// Default dead zone of gamepad sticks
+pub const DEFAULT_DEAD_ZONE: f32 = 0.1;
+
+/// Size of the joystick interface event record
+const RECORD_SIZE: usize = 8;
+/// Button event type
+const JS_EVENT_BUTTON: u8 = 0x01;
+/// Axis event type
+const JS_EVENT_AXIS: u8 = 0x02;
+/// Initial state event flag
+const JS_EVENT_INIT: u8 = 0x80;
+/// Maximal absolute value of an axis
+const AXIS_MAX: f32 = 32767.0;
+/// Number of joystick devices to watch
+#[cfg(target_os = "linux")]
+const MAX_DEVICES: u32 = 8;
+/// Interval between attempts to open disconnected devices
+#[cfg(target_os = "linux")]
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(1);
+/// Timeout of waiting for events, so the watcher notices that the service is dropped
+#[cfg(target_os = "linux")]
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Decoder of the joystick interface events stream
+pub struct GamepadDecoder {
+    gamepad: GamepadId,
+    dead_zone: f32,
+    axes: HashMap<u8, f32>,
+    hat: [i16; 2],
+}
+
+impl GamepadDecoder {
+    /// Constructs new decoder for the gamepad
+    pub fn new(gamepad: GamepadId) -> Self {
+        Self {
+            gamepad,
+            dead_zone: DEFAULT_DEAD_ZONE,
+            axes: HashMap::new(),
+            hat: [0; 2],
+        }
+    }
+
+    /// Sets dead zone of the sticks
+    pub fn dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Reads a record from the stream and decodes it
+    pub fn read(&mut self, reader: &mut impl Read) -> std::io::Result<Vec<Event>> {
+        let mut record = [0; RECORD_SIZE];
+        reader.read_exact(&mut record)?;
+        Ok(self.decode(&record))
+    }
+
+    /// Decodes the joystick interface event record
+    pub fn decode(&mut self, record: &[u8; RECORD_SIZE]) -> Vec<Event> {
+        let value = i16::from_ne_bytes([record[4], record[5]]);
+        let event_type = record[6] & !JS_EVENT_INIT;
+        let number = record[7];
+
+        match event_type {
+            JS_EVENT_BUTTON => {
+                let button = Button::Gamepad {
+                    gamepad: self.gamepad,
+                    button: button(number),
+                };
+                vec![if value != 0 {
+                    Event::ButtonPress { button, text: None }
+                } else {
+                    Event::ButtonRelease { button }
+                }]
+            }
+            JS_EVENT_AXIS => match number {
+                6 | 7 => self.decode_hat((number - 6) as usize, value),
+                _ => self.decode_axis(number, value),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn decode_axis(&mut self, number: u8, value: i16) -> Vec<Event> {
+        let axis = axis(number);
+        let value = value as f32 / AXIS_MAX;
+        let value = match axis {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                ((value + 1.0) / 2.0).clamp(0.0, 1.0)
+            }
+            // NOTE: device reports negative values for the up direction
+            GamepadAxis::LeftStickY | GamepadAxis::RightStickY => {
+                apply_dead_zone(-value, self.dead_zone)
+            }
+            _ => apply_dead_zone(value, self.dead_zone),
+        };
+
+        if self.axes.insert(number, value) == Some(value) {
+            return Vec::new();
+        }
+
+        vec![Event::GamepadAxis {
+            gamepad: self.gamepad,
+            axis,
+            value,
+        }]
+    }
+
+    fn decode_hat(&mut self, index: usize, value: i16) -> Vec<Event> {
+        let (negative, positive) = if index == 0 {
+            (GamepadButton::DPadLeft, GamepadButton::DPadRight)
+        } else {
+            (GamepadButton::DPadUp, GamepadButton::DPadDown)
+        };
+        let direction = |value: i16| match value.signum() {
+            -1 => Some(negative),
+            1 => Some(positive),
+            _ => None,
+        };
+
+        let released = direction(self.hat[index]);
+        let pressed = direction(value);
+        self.hat[index] = value;
+        if released == pressed {
+            return Vec::new();
+        }
+
+        let gamepad = self.gamepad;
+        released
+            .map(|button| Event::ButtonRelease {
+                button: Button::Gamepad { gamepad, button },
+            })
+            .into_iter()
+            .chain(pressed.map(|button| Event::ButtonPress {
+                button: Button::Gamepad { gamepad, button },
+                text: None,
+            }))
+            .collect()
+    }
+}
+
+fn button(number: u8) -> GamepadButton {
+    match number {
+        0 => GamepadButton::South,
+        1 => GamepadButton::East,
+        2 => GamepadButton::West,
+        3 => GamepadButton::North,
+        4 => GamepadButton::LeftBumper,
+        5 => GamepadButton::RightBumper,
+        6 => GamepadButton::Select,
+        7 => GamepadButton::Start,
+        8 => GamepadButton::Mode,
+        9 => GamepadButton::LeftThumb,
+        10 => GamepadButton::RightThumb,
+        _ => GamepadButton::Other(number),
+    }
+}
+
+fn axis(number: u8) -> GamepadAxis {
+    match number {
+        0 => GamepadAxis::LeftStickX,
+        1 => GamepadAxis::LeftStickY,
+        2 => GamepadAxis::LeftTrigger,
+        3 => GamepadAxis::RightStickX,
+        4 => GamepadAxis::RightStickY,
+        5 => GamepadAxis::RightTrigger,
+        _ => GamepadAxis::Other(number),
+    }
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= dead_zone {
+        0.0
+    } else {
+        value.signum() * ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0)
+    }
+}
+
+/// Joystick device, opened for non-blocking reads
+#[cfg(target_os = "linux")]
+struct Device {
+    gamepad: GamepadId,
+    path: String,
+    file: std::fs::File,
+    decoder: GamepadDecoder,
+}
+
+#[cfg(target_os = "linux")]
+impl Device {
+    fn open(gamepad: GamepadId, dead_zone: f32) -> Option<Self> {
+        let path = format!("/dev/input/js{}", gamepad);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&path)
+            .ok()?;
+        Some(Self {
+            gamepad,
+            path,
+            file,
+            decoder: GamepadDecoder::new(gamepad).dead_zone(dead_zone),
+        })
+    }
+
+    /// Reads all available events, returns false if the device was disconnected
+    fn read(&mut self, events: &mut Vec<Event>) -> bool {
+        loop {
+            match self.decoder.read(&mut self.file) {
+                Ok(decoded) => events.extend(decoded),
+                Err(error) => return error.kind() == std::io::ErrorKind::WouldBlock,
+            }
+        }
+    }
+}
+
+/// Gamepads service, collects events of connected gamepads
+pub struct Gamepads {
+    tx: mpsc::Sender<Event>,
+    rx: mpsc::Receiver<Event>,
+    dead_zone: f32,
+    running: Arc<AtomicBool>,
+}
+
+impl Drop for Gamepads {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Gamepads {
+    /// Constructs the service without any sources
+    pub fn new(dead_zone: f32) -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            tx,
+            rx,
+            dead_zone,
+            running: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Starts reading of the gamepad events from the stream, e.g. a recorded one
+    pub fn attach<R: Read + Send + 'static>(&self, gamepad: GamepadId, mut reader: R) {
+        let tx = self.tx.clone();
+        let running = Arc::clone(&self.running);
+        let mut decoder = GamepadDecoder::new(gamepad).dead_zone(self.dead_zone);
+        thread::spawn(move || {
+            tx.send(Event::GamepadConnected { gamepad }).ok();
+            while running.load(Ordering::Relaxed) {
+                match decoder.read(&mut reader) {
+                    Ok(events) => {
+                        for event in events {
+                            if tx.send(event).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            tx.send(Event::GamepadDisconnected { gamepad }).ok();
+        });
+    }
+
+    /// Starts watching of the joystick devices, connected gamepads are attached automatically
+    ///
+    /// A single thread discovers devices and waits for their events with a timeout, so it stops
+    /// shortly after the service is dropped.
+    #[cfg(target_os = "linux")]
+    pub fn watch_devices(&self) {
+        let tx = self.tx.clone();
+        let running = Arc::clone(&self.running);
+        let dead_zone = self.dead_zone;
+        thread::spawn(move || {
+            let mut devices: Vec<Device> = Vec::new();
+            let mut discovered_at: Option<Instant> = None;
+            while running.load(Ordering::Relaxed) {
+                if discovered_at.is_none_or(|instant| instant.elapsed() >= DISCOVERY_INTERVAL) {
+                    discovered_at = Some(Instant::now());
+                    for gamepad in 0..MAX_DEVICES {
+                        if devices.iter().any(|device| device.gamepad == gamepad) {
+                            continue;
+                        }
+                        if let Some(device) = Device::open(gamepad, dead_zone) {
+                            log::info!("Gamepad connected: {}", device.path);
+                            if tx.send(Event::GamepadConnected { gamepad }).is_err() {
+                                return;
+                            }
+                            devices.push(device);
+                        }
+                    }
+                }
+
+                if devices.is_empty() {
+                    thread::sleep(POLL_TIMEOUT);
+                    continue;
+                }
+
+                let mut fds = devices
+                    .iter()
+                    .map(|device| libc::pollfd {
+                        fd: device.file.as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    })
+                    .collect::<Vec<_>>();
+                // NOTE: descriptors are owned by `devices` and stay open during the call
+                let ready = unsafe {
+                    libc::poll(
+                        fds.as_mut_ptr(),
+                        fds.len() as libc::nfds_t,
+                        POLL_TIMEOUT.as_millis() as libc::c_int,
+                    )
+                };
+                if ready <= 0 {
+                    continue;
+                }
+
+                let mut events = Vec::new();
+                let mut disconnected = Vec::new();
+                for (device, fd) in devices.iter_mut().zip(fds.iter()) {
+                    if fd.revents != 0 && !device.read(&mut events) {
+                        log::info!("Gamepad disconnected: {}", device.path);
+                        events.push(Event::GamepadDisconnected {
+                            gamepad: device.gamepad,
+                        });
+                        disconnected.push(device.gamepad);
+                    }
+                }
+                devices.retain(|device| !disconnected.contains(&device.gamepad));
+                for event in events {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Gamepads are supported on Linux only
+    #[cfg(not(target_os = "linux"))]
+    pub fn watch_devices(&self) {
+        log::warn!("Gamepads are not supported on this platform");
+    }
+
+    /// Returns iterator over received events
+    pub fn poll(&self) -> mpsc::TryIter<'_, Event> {
+        self.rx.try_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GamepadDecoder;
+    use crate::window::event::{Button, Event, GamepadAxis, GamepadButton};
+
+    fn record(value: i16, event_type: u8, number: u8) -> [u8; 8] {
+        let value = value.to_ne_bytes();
+        [0, 0, 0, 0, value[0], value[1], event_type, number]
+    }
+
+    #[test]
+    fn recorded_stream_is_decoded() {
+        let stream = [
+            // initial state
+            record(0, 0x81, 0),
+            record(-32767, 0x82, 2),
+            // South pressed
+            record(1, 0x01, 0),
+            // left stick inside of the dead zone
+            record(1000, 0x02, 0),
+            // left stick up
+            record(-32767, 0x02, 1),
+            // left trigger pressed
+            record(32767, 0x02, 2),
+            // d-pad left, then right
+            record(-32767, 0x02, 6),
+            record(32767, 0x02, 6),
+            // South released
+            record(0, 0x01, 0),
+        ]
+        .concat();
+
+        let mut decoder = GamepadDecoder::new(1).dead_zone(0.2);
+        let mut reader = stream.as_slice();
+        let mut events = Vec::new();
+        while let Ok(decoded) = decoder.read(&mut reader) {
+            events.extend(decoded);
+        }
+
+        let south = Button::Gamepad {
+            gamepad: 1,
+            button: GamepadButton::South,
+        };
+        let axis = |axis, value| Event::GamepadAxis {
+            gamepad: 1,
+            axis,
+            value,
+        };
+        let dpad = |button| Button::Gamepad { gamepad: 1, button };
+
+        assert_eq!(
+            events,
+            vec![
+                Event::ButtonRelease { button: south },
+                axis(GamepadAxis::LeftTrigger, 0.0),
+                Event::ButtonPress {
+                    button: south,
+                    text: None
+                },
+                axis(GamepadAxis::LeftStickX, 0.0),
+                axis(GamepadAxis::LeftStickY, 1.0),
+                axis(GamepadAxis::LeftTrigger, 1.0),
+                Event::ButtonPress {
+                    button: dpad(GamepadButton::DPadLeft),
+                    text: None
+                },
+                Event::ButtonRelease {
+                    button: dpad(GamepadButton::DPadLeft)
+                },
+                Event::ButtonPress {
+                    button: dpad(GamepadButton::DPadRight),
+                    text: None
+                },
+                Event::ButtonRelease { button: south },
+            ]
+        );
+    }
+}
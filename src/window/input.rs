@@ -8,6 +8,8 @@ pub use super::event::{
     Button,
     // DragAndDrop,
     Event,
+    GamepadAxis,
+    GamepadId,
     // KeyCode,
     Modifiers,
     MouseScroll,
@@ -31,6 +33,17 @@ pub struct Input {
     pub mouse_move_delta: ScreenVector,
     pub mouse_scroll_delta_lines: ScreenVector,
     pub mouse_scroll_delta_pixels: ScreenVector,
+    pub gamepad_axes: HashMap<(GamepadId, GamepadAxis), f32>,
+}
+
+impl Input {
+    /// Returns value of the gamepad axis
+    pub fn gamepad_axis(&self, gamepad: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepad_axes
+            .get(&(gamepad, axis))
+            .copied()
+            .unwrap_or(0.0)
+    }
 }
 
 #[derive(Default)]
@@ -38,6 +51,7 @@ pub struct ReadInput {
     modifiers: Modifiers,
     hold: HashMap<Button, Instant>,
     mouse_position: ScreenVector,
+    gamepad_axes: HashMap<(GamepadId, GamepadAxis), f32>,
 }
 
 impl ReadInput {
@@ -46,12 +60,10 @@ impl ReadInput {
     }
 }
 
-impl Task for ReadInput {
-    type Context = (Take<All<Event>>, Any<Frame>);
-    type Output = Input; // :)
-    fn run(&mut self, (mut events, _): Self::Context) -> Self::Output {
-        let capacity = events.len();
-        let mut list = Vec::with_capacity(capacity);
+impl ReadInput {
+    /// Aggregates events of the frame into the input
+    pub fn read(&mut self, events: impl IntoIterator<Item = Event>) -> Input {
+        let mut list = Vec::new();
 
         let mut input_text = String::with_capacity(8);
         let mut mouse_move_delta = ScreenVector::default();
@@ -59,7 +71,7 @@ impl Task for ReadInput {
         let mut mouse_scroll_delta_pixels = ScreenVector::default();
         let mut mouse_position = self.mouse_position;
 
-        for event in events.drain() {
+        for event in events {
             match &event {
                 Event::ModifiersChange { modifiers } => {
                     self.modifiers = *modifiers;
@@ -103,11 +115,30 @@ impl Task for ReadInput {
                     mouse_position.horizontal = *horizontal;
                     mouse_position.vertical = *vertical;
                 }
+                Event::GamepadAxis {
+                    gamepad,
+                    axis,
+                    value,
+                } => {
+                    if *value != 0.0 {
+                        self.gamepad_axes.insert((*gamepad, *axis), *value);
+                    } else {
+                        self.gamepad_axes.remove(&(*gamepad, *axis));
+                    }
+                }
+                Event::GamepadDisconnected { gamepad } => {
+                    self.gamepad_axes.retain(|(id, _), _| id != gamepad);
+                    self.hold.retain(|button, _| {
+                        !matches!(button, Button::Gamepad { gamepad: id, .. } if id == gamepad)
+                    });
+                }
                 _ => {}
             }
             list.push(event);
         }
 
+        self.mouse_position = mouse_position;
+
         Input {
             events: list,
             modifiers: self.modifiers,
@@ -116,11 +147,20 @@ impl Task for ReadInput {
             mouse_move_delta,
             mouse_scroll_delta_lines,
             mouse_scroll_delta_pixels,
+            gamepad_axes: self.gamepad_axes.clone(),
             text: input_text,
         }
     }
 }
 
+impl Task for ReadInput {
+    type Context = (Take<All<Event>>, Any<Frame>);
+    type Output = Input; // :)
+    fn run(&mut self, (mut events, _): Self::Context) -> Self::Output {
+        self.read(events.drain())
+    }
+}
+
 /*
 #[inline]
 fn is_printable(chr: char) -> bool {
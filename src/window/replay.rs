@@ -0,0 +1,245 @@
+//! Input recording and replay
+//!
+//! Recording is stored as JSON lines, one [`RecordedFrame`] per line, so an interrupted run
+//! still leaves a readable recording. Replayed frames get the recorded deltas, see
+//! [`super::add_input_tasks`].
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::event::Event;
+use super::input::{Input, ReadInput};
+use crate::graphics::Frame;
+use crate::log;
+use crate::tasks::{All, Any, Take, Task};
+
+/// Source of the input events
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum InputSource {
+    /// Events of the window and gamepads
+    #[default]
+    Live,
+    /// Events of the window and gamepads, recorded into the file
+    Record(PathBuf),
+    /// Events replayed from the file, live events are discarded
+    Replay(PathBuf),
+}
+
+/// Input events of a frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Time passed since last frame
+    pub delta: Duration,
+    /// Events of the frame
+    pub events: Vec<Event>,
+}
+
+/// Recorded input
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Recording {
+    /// Recorded frames
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    /// Loads recording from the file
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads recording from the reader
+    pub fn read(reader: impl BufRead) -> std::io::Result<Self> {
+        let mut frames = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            frames.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self { frames })
+    }
+
+    /// Writes recording into the writer
+    pub fn write(&self, mut writer: impl Write) -> std::io::Result<()> {
+        for frame in self.frames.iter() {
+            write_frame(&mut writer, frame)?;
+        }
+        writer.flush()
+    }
+}
+
+fn write_frame(writer: &mut impl Write, frame: &RecordedFrame) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *writer, frame)?;
+    writer.write_all(b"\n")
+}
+
+/// Output of the [`RecordInput`] task
+pub struct InputRecorded {
+    /// Number of recorded frames
+    pub frames: u64,
+}
+
+/// Task, responsible for recording of the input
+pub struct RecordInput {
+    writer: Box<dyn Write + Send + Sync>,
+    frames: u64,
+}
+
+impl RecordInput {
+    /// Constructs the task recording into a new file
+    pub fn new(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::with_writer(BufWriter::new(file)))
+    }
+
+    /// Constructs the task recording into the writer
+    pub fn with_writer(writer: impl Write + Send + Sync + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+            frames: 0,
+        }
+    }
+}
+
+impl Task for RecordInput {
+    type Context = (Any<Input>, Any<Frame>);
+    type Output = InputRecorded;
+
+    fn run(&mut self, (input, frame): Self::Context) -> Self::Output {
+        let recorded_frame = RecordedFrame {
+            delta: frame.delta,
+            events: input.events.clone(),
+        };
+        // NOTE: flush every frame to keep the recording in case of crash
+        match write_frame(&mut self.writer, &recorded_frame).and_then(|_| self.writer.flush()) {
+            Ok(()) => self.frames += 1,
+            Err(error) => log::error!("Could not record input: {}", error),
+        }
+        InputRecorded {
+            frames: self.frames,
+        }
+    }
+}
+
+/// Task, replaying recorded input instead of [`ReadInput`]
+pub struct ReplayInput {
+    frames: std::vec::IntoIter<RecordedFrame>,
+    read_input: ReadInput,
+    finished: bool,
+}
+
+impl ReplayInput {
+    /// Constructs the task replaying the recording
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            frames: recording.frames.into_iter(),
+            read_input: ReadInput::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns true if all recorded frames were replayed
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns input of the next recorded frame
+    pub fn replay(&mut self, delta: Duration) -> Input {
+        match self.frames.next() {
+            Some(recorded_frame) => {
+                if recorded_frame.delta != delta {
+                    log::warn!(
+                        "Replayed frame delta {:?} differs from recorded {:?}",
+                        delta,
+                        recorded_frame.delta
+                    );
+                }
+                self.read_input.read(recorded_frame.events)
+            }
+            None => {
+                if !self.finished {
+                    log::info!("Input replay finished");
+                    self.finished = true;
+                }
+                self.read_input.read(std::iter::empty())
+            }
+        }
+    }
+}
+
+impl Task for ReplayInput {
+    type Context = (Take<All<Event>>, Any<Frame>);
+    type Output = Input;
+
+    fn run(&mut self, (mut events, frame): Self::Context) -> Self::Output {
+        // live events are discarded
+        events.drain().for_each(drop);
+        self.replay(frame.delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{RecordedFrame, Recording, ReplayInput};
+    use crate::window::event::{Button, Event, KeyCode};
+    use crate::window::ReadInput;
+
+    #[test]
+    fn recorded_input_is_replayed() {
+        let delta = Duration::from_millis(16);
+        let key = Button::Key {
+            key_code: Some(KeyCode::Space),
+            scan_code: 57,
+        };
+        let frames = vec![
+            vec![
+                Event::ButtonPress {
+                    button: key,
+                    text: Some(String::from(" ")),
+                },
+                Event::MouseMove {
+                    horizontal: 2.0,
+                    vertical: -1.0,
+                },
+            ],
+            vec![],
+            vec![Event::ButtonRelease { button: key }],
+        ];
+
+        let recording = Recording {
+            frames: frames
+                .iter()
+                .map(|events| RecordedFrame {
+                    delta,
+                    events: events.clone(),
+                })
+                .collect(),
+        };
+        let mut buffer = Vec::new();
+        recording.write(&mut buffer).unwrap();
+        let loaded = Recording::read(buffer.as_slice()).unwrap();
+        assert_eq!(loaded, recording);
+
+        let mut read_input = ReadInput::new();
+        let mut replay_input = ReplayInput::new(loaded);
+        for events in frames {
+            let live = read_input.read(events);
+            let replayed = replay_input.replay(delta);
+            assert_eq!(replayed.events, live.events);
+            assert_eq!(replayed.text, live.text);
+            assert_eq!(
+                replayed.hold.keys().collect::<Vec<_>>(),
+                live.hold.keys().collect::<Vec<_>>()
+            );
+            assert!(!replay_input.is_finished());
+        }
+        assert!(replay_input.replay(delta).events.is_empty());
+        assert!(replay_input.is_finished());
+    }
+}
@@ -1,12 +1,33 @@
+mod camera;
+mod commands;
+mod events;
+mod filters;
+mod handles;
+mod light;
+mod resources;
+mod snapshot;
 mod storage;
 
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::{any::TypeId, collections::HashMap, marker::PhantomData};
 
 use crate::recursive;
 use crate::utils::{Id, Lock, TypeLock};
+pub use camera::{Camera, Lens, View};
+pub use commands::{ApplyCommands, Commands, CommandsApplied};
+pub use events::{ObserveWorld, WorldEvent, WorldEvents};
+pub use filters::{Added, Changed, OptionalSelector, With, Without};
+pub use handles::{EntityKey, Handle};
+pub use light::{Light, LightUniform, Position as LightPosition};
+pub use resources::{Res, ResMut};
+pub use snapshot::{EntitySnapshot, Registry, Snapshot, SnapshotFormat};
 pub use storage::{Entity, IntoEntity};
 
+/// Default number of entity slots processed at once by [`World::execute_parallel`]
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
 #[derive(Default, Debug, Eq, PartialEq)]
 struct Index {
     /// storage::Container Index
@@ -19,12 +40,22 @@ struct Index {
 pub struct World {
     /// Entities container grouped by archetypes
     content: Vec<storage::Container>,
-    /// Index of the container that holds Entity
-    index: HashMap<Id<Entity>, Index>,
+    /// Slots of entities by their IDs
+    index: HashMap<Id<Entity>, u32>,
+    /// Locations of entities, addressed by handles
+    slots: Vec<handles::Slot>,
+    /// Free slots
+    free: Vec<u32>,
     // /// Spawn counter for Entity ID generation
     // next_id: u64,
     /// Lock for multithread safety
     lock: Arc<(Mutex<TypeLock>, Condvar)>,
+    /// Current change tick
+    change_tick: AtomicU64,
+    /// Recorded lifecycle events, if the world is observed
+    events: Mutex<Option<Vec<WorldEvent>>>,
+    /// Singleton resources by their types
+    resources: HashMap<TypeId, resources::Resource>,
 }
 
 impl World {
@@ -33,8 +64,58 @@ impl World {
         Self {
             content: Vec::new(),
             index: HashMap::new(),
+            slots: Vec::new(),
+            free: Vec::new(),
             // next_id: 1,
             lock: Arc::new((Mutex::new(TypeLock::new()), Condvar::new())),
+            change_tick: AtomicU64::new(1),
+            events: Mutex::new(None),
+            resources: HashMap::new(),
+        }
+    }
+
+    /// Returns current change tick
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /// Advances the change tick and returns the previous one
+    ///
+    /// A consumer of changes stores the returned tick to pass it to [`World::query_since`] next
+    /// time, so it gets all changes made after the call.
+    pub fn advance_tick(&self) -> u64 {
+        self.change_tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Starts recording of lifecycle events, see [`World::take_events`]
+    pub fn observe(&self) {
+        let mut events = self.events.lock().unwrap();
+        if events.is_none() {
+            *events = Some(Vec::new());
+        }
+    }
+
+    /// Returns lifecycle events recorded since the previous call
+    pub fn take_events(&self) -> Vec<WorldEvent> {
+        self.events
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Records the event, if the world is observed
+    fn emit(&mut self, event: WorldEvent) {
+        if let Some(events) = self.events.get_mut().unwrap().as_mut() {
+            events.push(event);
+        }
+    }
+
+    fn ticks(&self, since: u64) -> QueryTicks {
+        QueryTicks {
+            since,
+            current: self.change_tick(),
         }
     }
 
@@ -52,7 +133,7 @@ impl World {
     /// Returns an iterator over entity IDs
     ///
     /// Actual spawning of the entity occurs on iterator consuming
-    pub fn spawn<T, I>(&mut self, entries: I) -> SpawnIter<I, T>
+    pub fn spawn<T, I>(&mut self, entries: I) -> SpawnIter<'_, I, T>
     where
         T: IntoEntity,
         I: IntoIterator<Item = T>,
@@ -64,91 +145,306 @@ impl World {
         }
     }
 
-    fn lock<'w, Q: Query<'w>>(&self) {
-        let locks = Q::locks();
+    fn lock<'w, Q: Query<'w>>(&self) -> LockGuard {
+        self.acquire(Q::locks())
+    }
+
+    fn acquire(&self, locks: Vec<Lock>) -> LockGuard {
         let (mutex, cvar) = &*self.lock;
         let mut lock_manager = mutex.lock().unwrap();
         while !lock_manager.lock(&locks) {
             lock_manager = cvar.wait(lock_manager).unwrap();
         }
+        LockGuard {
+            locks,
+            lock_manager: Arc::clone(&self.lock),
+        }
     }
 
     /// Returns iterator over entities defined by Query pattern
+    ///
+    /// [`Changed`] and [`Added`] filters report all components, see [`World::query_since`]
     pub fn query<'w, Q>(
         &'w self,
-    ) -> impl Iterator<Item = <<Q as Query>::Iter as Iterator>::Item> + 'w
+    ) -> impl Iterator<Item = <<Q as Query<'w>>::Iter as Iterator>::Item> + 'w
+    where
+        Q: Query<'w>,
+    {
+        self.query_since::<Q>(0)
+    }
+
+    /// Returns iterator over entities defined by Query pattern, [`Changed`] and [`Added`]
+    /// filters report components changed or added after the `since` tick
+    pub fn query_since<'w, Q>(
+        &'w self,
+        since: u64,
+    ) -> impl Iterator<Item = <<Q as Query<'w>>::Iter as Iterator>::Item> + 'w
     where
         Q: Query<'w>,
     {
-        self.lock::<Q>();
+        let guard = self.lock::<Q>();
+        let ticks = self.ticks(since);
 
         let iter = self
             .content
             .iter()
             .filter(|container| Q::matches(container))
-            .flat_map(|container| Q::select(container));
+            .flat_map(move |container| Q::select(container, 0..container.slots(), ticks));
 
         QueryIter {
             iter,
-            locks: Q::locks(),
-            lock_manager: Arc::clone(&self.lock),
+            _guard: guard,
         }
     }
 
     /// Execute a system for each entity in the world
+    ///
+    /// See [`World::execute_parallel`] for the multithreaded variant
     pub fn execute<'w, Q, S>(&'w self, system: S)
     where
         Q: Query<'w> + 'w,
-        S: Fn(<<Q as Query<'_>>::Iter as Iterator>::Item),
+        S: Fn(<<Q as Query<'w>>::Iter as Iterator>::Item),
     {
-        // TODO: parallelize
         for item in self.query::<Q>() {
             system(item);
         }
     }
 
+    /// Execute a system for each entity in the world using all available cores
+    ///
+    /// Entities are split into chunks of [`DEFAULT_CHUNK_SIZE`] slots, order of the system calls
+    /// is not defined.
+    pub fn execute_parallel<'w, Q, S>(&'w self, system: S)
+    where
+        Q: Query<'w> + 'w,
+        S: Fn(<<Q as Query<'w>>::Iter as Iterator>::Item) + Sync,
+    {
+        self.execute_chunked::<Q, S>(DEFAULT_CHUNK_SIZE, system);
+    }
+
+    /// Execute a system for each entity in the world, processing chunks of `chunk_size` entity
+    /// slots in parallel
+    ///
+    /// Query locks are held until all chunks are processed.
+    pub fn execute_chunked<'w, Q, S>(&'w self, chunk_size: usize, system: S)
+    where
+        Q: Query<'w> + 'w,
+        S: Fn(<<Q as Query<'w>>::Iter as Iterator>::Item) + Sync,
+    {
+        let _guard = self.lock::<Q>();
+        let ticks = self.ticks(0);
+        let chunks = self.chunks::<Q>(chunk_size.max(1));
+        let workers = std::thread::available_parallelism()
+            .map(|value| value.get())
+            .unwrap_or(1)
+            .min(chunks.len());
+
+        if workers <= 1 {
+            for (container_index, range) in chunks {
+                Q::select(&self.content[container_index], range, ticks).for_each(&system);
+            }
+            return;
+        }
+
+        let next_chunk = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while let Some((container_index, range)) =
+                        chunks.get(next_chunk.fetch_add(1, Ordering::Relaxed))
+                    {
+                        Q::select(&self.content[*container_index], range.clone(), ticks)
+                            .for_each(&system);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Splits matching containers into ranges of entity slots
+    fn chunks<'w, Q: Query<'w>>(&'w self, chunk_size: usize) -> Vec<(usize, Range<usize>)> {
+        self.content
+            .iter()
+            .enumerate()
+            .filter(|(_, container)| Q::matches(container))
+            .flat_map(|(container_index, container)| {
+                let slots = container.slots();
+                (0..slots)
+                    .step_by(chunk_size)
+                    .map(move |start| (container_index, start..slots.min(start + chunk_size)))
+            })
+            .collect()
+    }
+
     /// Get componets dor specified entity
-    pub fn get<'w, Q>(&'w self, id: &Id<Entity>) -> Option<Q>
+    ///
+    /// Locks of the query are held until the returned [`Pick`] is dropped.
+    pub fn get<'w, Q>(&'w self, key: impl EntityKey) -> Option<Pick<'w, Q>>
     where
         Q: Query<'w>,
     {
-        self.lock::<Q>();
-        self.index
-            .get(id)
-            .map(|index| Q::pick(&self.content[index.container], index.address))
+        let guard = self.lock::<Q>();
+        key.slot(self)
+            .map(|slot| &self.entry(slot).index)
+            .filter(|index| Q::matches(&self.content[index.container]))
+            .and_then(|index| Q::pick(&self.content[index.container], index.address, self.ticks(0)))
+            .map(|value| Pick {
+                value,
+                _guard: guard,
+                _lifetime: PhantomData,
+            })
     }
 
     /// Exiles an entity from the world
-    pub fn exile(&mut self, id: &Id<Entity>) -> Option<Entity> {
-        self.index
-            .get(id)
-            .map(|index| self.content[index.container].remove(index.address))
+    pub fn exile(&mut self, key: impl EntityKey) -> Option<Entity> {
+        let slot = key.slot(self)?;
+        let mut entity = self.take(slot);
+        entity.reset_ticks();
+        let id = self.release(slot);
+        self.emit(WorldEvent::Exiled(id));
+        Some(entity)
+    }
+
+    /// Takes the entity out of its container, keeping the slot and change ticks of components
+    fn take(&mut self, slot: usize) -> Entity {
+        let index = &self.entry(slot).index;
+        let (container, address) = (index.container, index.address);
+        let (entity, moved) = self.content[container].remove(address);
+        // the last entity of the container was moved into the place of the taken one
+        if let Some(moved) = moved {
+            if let Some(&moved_slot) = self.index.get(&moved) {
+                if let Some(entry) = self.slots[moved_slot as usize].entry.as_mut() {
+                    entry.index.address = address;
+                }
+            }
+        }
+        entity
+    }
+
+    /// Inserts the component into the entity, replacing the existing one of the same type
+    ///
+    /// Returns false if the entity does not exist. The `Id<Entity>` component can not be
+    /// replaced.
+    pub fn insert<C: std::any::Any>(&mut self, key: impl EntityKey, component: C) -> bool {
+        if TypeId::of::<C>() == TypeId::of::<Id<Entity>>() {
+            return false;
+        }
+        let slot = match key.slot(self) {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let entry = self.entry(slot);
+        let (id, container, address) = (entry.id, entry.index.container, entry.index.address);
+        let container = &mut self.content[container];
+        if container.has(TypeId::of::<C>()) {
+            // NOTE: world is borrowed exclusively, so there are no other references
+            let tick = self.change_tick.load(Ordering::Relaxed);
+            if let Some(current) = unsafe { container.get_mut::<C>(address, tick) } {
+                *current = component;
+            }
+            return true;
+        }
+        let entity = self.take(slot).with(component);
+        self.relocate(slot, entity);
+        self.emit(WorldEvent::ComponentAdded {
+            entity: id,
+            component: TypeId::of::<C>(),
+        });
+        true
+    }
+
+    /// Removes the component from the entity and returns it
+    ///
+    /// The `Id<Entity>` component can not be removed.
+    pub fn remove<C: std::any::Any>(&mut self, key: impl EntityKey) -> Option<C> {
+        if TypeId::of::<C>() == TypeId::of::<Id<Entity>>() {
+            return None;
+        }
+        let slot = key.slot(self)?;
+        let entry = self.entry(slot);
+        let id = entry.id;
+        if !self.content[entry.index.container].has(TypeId::of::<C>()) {
+            return None;
+        }
+        let mut entity = self.take(slot);
+        let component = entity.remove::<C>();
+        self.relocate(slot, entity);
+        self.emit(WorldEvent::ComponentRemoved {
+            entity: id,
+            component: TypeId::of::<C>(),
+        });
+        component
+    }
+
+    /// Returns true if the entity exists
+    pub fn contains(&self, key: impl EntityKey) -> bool {
+        key.slot(self).is_some()
+    }
+
+    /// Returns mutable reference to the component of the entity, marking it as changed
+    ///
+    /// World is borrowed exclusively, so unlike [`World::get`] no locks are taken. The
+    /// `Id<Entity>` component can not be borrowed mutably.
+    pub fn component_mut<C: std::any::Any>(&mut self, key: impl EntityKey) -> Option<&mut C> {
+        if TypeId::of::<C>() == TypeId::of::<Id<Entity>>() {
+            return None;
+        }
+        let slot = key.slot(self)?;
+        let index = &self.entry(slot).index;
+        let tick = self.change_tick();
+        unsafe { self.content[index.container].get_mut::<C>(index.address, tick) }
+    }
+
+    /// Spawns the entity with the ID
+    fn spawn_with_id(&mut self, id: Id<Entity>, entity: Entity) {
+        let index = self.store(entity.with(id));
+        self.allocate(id, index);
+        self.emit(WorldEvent::Spawned(id));
+    }
+
+    /// Stores the entity, taken out of the slot, in the container of its archetype
+    fn relocate(&mut self, slot: usize, entity: Entity) {
+        let index = self.store(entity);
+        if let Some(entry) = self.slots[slot].entry.as_mut() {
+            entry.index = index;
+        }
+    }
+
+    /// Stores the entity in the container of its archetype and returns its location
+    fn store(&mut self, entity: Entity) -> Index {
+        let container = self.container(&entity.archetype());
+        let tick = self.change_tick();
+        let address = self.content[container].store(entity, tick);
+        Index { container, address }
     }
 
     /// Clear all entities from the world
     pub fn clear(&mut self) {
+        for slot in 0..self.slots.len() {
+            if self.slots[slot].entry.is_some() {
+                let id = self.release(slot);
+                self.emit(WorldEvent::Exiled(id));
+            }
+        }
         self.content.clear();
-        self.index.clear();
     }
 
-    /// Clear entities from the world and reset to initial state
+    /// Clear entities and resources from the world and reset to initial state
     pub fn reset(&mut self) {
         self.clear();
+        self.resources.clear();
         // self.next_id = 0;
     }
 
-    fn find_container_for_entity(&self, entity: &Entity) -> Option<usize> {
+    /// Returns index of the container storing exactly the archetype, creating it if necessary
+    fn container(&mut self, archetype: &[storage::ComponentInfo]) -> usize {
         self.content
             .iter()
-            .enumerate()
-            .find(|(_, container)| container.matches(&mut entity.archetype()))
-            .map(|(index, _)| index)
-    }
-
-    fn create_container_for_entity(&mut self, entity: &Entity) -> usize {
-        let index = self.content.len();
-        self.content.push(storage::Container::from(entity));
-        index
+            .position(|container| container.matches(archetype))
+            .unwrap_or_else(|| {
+                self.content.push(storage::Container::new(archetype));
+                self.content.len() - 1
+            })
     }
 
     // fn next_id(&mut self) -> u64 {
@@ -158,47 +454,84 @@ impl World {
     // }
 }
 
+/// Change ticks, used by queries to borrow components and detect their changes
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct QueryTicks {
+    /// Components changed after this tick are reported by [`Changed`] and [`Added`] filters
+    pub since: u64,
+    /// Mutably borrowed components are marked as changed at this tick
+    pub current: u64,
+}
+
 /// Abstraction for queries inoked by [`World::query`]
 pub trait Query<'w> {
     type Iter: Iterator + 'w;
     /// Returns vector of locks necesary for the query execution
     fn locks() -> Vec<Lock>;
-    /// Selects entities from container
-    fn select(container: &'w storage::Container) -> Self::Iter;
+    /// Selects entities from the range of container slots
+    fn select(
+        container: &'w storage::Container,
+        range: Range<usize>,
+        ticks: QueryTicks,
+    ) -> Self::Iter;
     /// Checks if [`Query`] matches the [`storage::Container`]
     fn matches(container: &'w storage::Container) -> bool;
-    /// Pick specific entity by its index in container
-    fn pick(container: &'w storage::Container, entity_index: usize) -> Self;
+    /// Pick specific entity by its index in container, if it passes filters of the query
+    fn pick(
+        container: &'w storage::Container,
+        entity_index: usize,
+        ticks: QueryTicks,
+    ) -> Option<Self>
+    where
+        Self: Sized;
 }
 
-/// Trait defenition of Selector to control mutability of borrows
-pub trait Selector<'w> {
-    type Iter: Iterator + 'w;
-    type Component: std::any::Any;
-
-    fn borrow(container: &'w storage::Container) -> Self::Iter;
-    fn borrow_one(container: &'w storage::Container, entity_index: usize) -> Self;
-    fn lock() -> Lock;
+/// Trait defenition of Selector to control mutability of borrows and filtering of entities
+pub trait Selector<'w>: Sized {
+    /// Row of the selector for a single container slot
+    type Row: Row<Value = Self>;
+    /// Iterator over rows of container slots
+    type Iter: Iterator<Item = Self::Row> + 'w;
+
+    fn borrow(
+        container: &'w storage::Container,
+        range: Range<usize>,
+        ticks: QueryTicks,
+    ) -> Self::Iter;
+    fn borrow_one(
+        container: &'w storage::Container,
+        entity_index: usize,
+        ticks: QueryTicks,
+    ) -> Self::Row;
+    fn matches(container: &'w storage::Container) -> bool;
+    fn lock() -> Option<Lock>;
 }
 
 impl<'w, C> Selector<'w> for &'w C
 where
     C: Send + Sync + 'static,
 {
-    // type Iter = std::slice::Iter<'w, C>;
-    type Iter = storage::Iter<'w, C>;
-    type Component = C;
+    type Row = Option<Self>;
+    type Iter = std::iter::Map<storage::Iter<'w, C>, fn(&'w C) -> Option<&'w C>>;
 
-    fn borrow(container: &'w storage::Container) -> Self::Iter {
-        container.iter::<C>()
+    fn borrow(container: &'w storage::Container, range: Range<usize>, _: QueryTicks) -> Self::Iter {
+        container.iter_range::<C>(range).map(Some)
     }
 
-    fn borrow_one(container: &'w storage::Container, entity_index: usize) -> Self {
-        container.get::<C>(entity_index).unwrap()
+    fn borrow_one(
+        container: &'w storage::Container,
+        entity_index: usize,
+        _: QueryTicks,
+    ) -> Option<Self> {
+        container.get::<C>(entity_index)
     }
 
-    fn lock() -> Lock {
-        Lock::ReadOnly(TypeId::of::<C>())
+    fn matches(container: &'w storage::Container) -> bool {
+        container.contains(TypeId::of::<C>())
+    }
+
+    fn lock() -> Option<Lock> {
+        Some(Lock::ReadOnly(TypeId::of::<C>()))
     }
 }
 
@@ -206,19 +539,33 @@ impl<'w, C> Selector<'w> for &'w mut C
 where
     C: Send + Sync + 'static,
 {
-    type Iter = storage::IterMut<'w, C>;
-    type Component = C;
+    type Row = RowMut<'w, C>;
+    type Iter =
+        std::iter::Map<storage::IterMut<'w, C>, fn(storage::ComponentMut<'w, C>) -> RowMut<'w, C>>;
+
+    fn borrow(
+        container: &'w storage::Container,
+        range: Range<usize>,
+        ticks: QueryTicks,
+    ) -> Self::Iter {
+        unsafe { container.iter_mut_range::<C>(range, ticks.current) }
+            .map(|component| RowMut(Some(component)))
+    }
 
-    fn borrow(container: &'w storage::Container) -> Self::Iter {
-        unsafe { container.iter_mut::<C>() }
+    fn borrow_one(
+        container: &'w storage::Container,
+        entity_index: usize,
+        ticks: QueryTicks,
+    ) -> Self::Row {
+        RowMut(unsafe { container.borrow_mut::<C>(entity_index, ticks.current) })
     }
 
-    fn borrow_one(container: &'w storage::Container, entity_index: usize) -> Self {
-        unsafe { container.get_mut::<C>(entity_index).unwrap() }
+    fn matches(container: &'w storage::Container) -> bool {
+        container.contains(TypeId::of::<C>())
     }
 
-    fn lock() -> Lock {
-        Lock::ReadWrite(TypeId::of::<C>())
+    fn lock() -> Option<Lock> {
+        Some(Lock::ReadWrite(TypeId::of::<C>()))
     }
 }
 
@@ -228,6 +575,45 @@ pub struct Zipper<'w, T> {
     _lifetime: PhantomData<&'w ()>,
 }
 
+/// Row of a selector iterator
+///
+/// Values are taken only after rows of all selectors are checked, so mutable borrows mark
+/// components as changed only for entities, that pass all filters of the query.
+pub trait Row {
+    type Value;
+    /// Returns false if the entity is filtered out
+    fn is_selected(&self) -> bool;
+    /// Returns value of the row or `None` if the entity is filtered out
+    fn value(self) -> Option<Self::Value>;
+}
+
+impl<T> Row for Option<T> {
+    type Value = T;
+
+    fn is_selected(&self) -> bool {
+        self.is_some()
+    }
+
+    fn value(self) -> Option<T> {
+        self
+    }
+}
+
+/// Row of the mutable selector, marking the component as changed when its value is taken
+pub struct RowMut<'w, C>(Option<storage::ComponentMut<'w, C>>);
+
+impl<'w, C> Row for RowMut<'w, C> {
+    type Value = &'w mut C;
+
+    fn is_selected(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn value(self) -> Option<&'w mut C> {
+        self.0.map(|component| component.take())
+    }
+}
+
 macro_rules! impl_queries {
     ($($i: ident),*) => {
         impl<'w, $($i),*> Query<'w> for ($($i,)*)
@@ -236,14 +622,26 @@ macro_rules! impl_queries {
         {
             type Iter = Zipper<'w, ($($i::Iter,)*)>;
 
-            fn pick(container: &'w storage::Container, entity_index: usize) -> ($($i,)*) {
-                ($({$i::borrow_one(container, entity_index)},)*)
+            #[allow(non_snake_case)]
+            fn pick(
+                container: &'w storage::Container,
+                entity_index: usize,
+                ticks: QueryTicks,
+            ) -> Option<($($i,)*)> {
+                let ($($i,)*) = ($($i::borrow_one(container, entity_index, ticks),)*);
+                if !($($i.is_selected())&&*) {
+                    return None;
+                }
+                Some(($($i.value()?,)*))
             }
 
-            fn select(container: &'w storage::Container) -> Self::Iter {
+            fn select(
+                container: &'w storage::Container,
+                range: Range<usize>,
+                ticks: QueryTicks,
+            ) -> Self::Iter {
                 Zipper {
-                    tuple: ($({$i::borrow(container)},)*),
-                    // tuple: ($(container.get::<$i::Component>().unwrap().into_iter(),)*),
+                    tuple: ($({$i::borrow(container, range.clone(), ticks)},)*),
                     _lifetime: PhantomData,
                 }
             }
@@ -251,56 +649,75 @@ macro_rules! impl_queries {
             fn matches(container: &'w storage::Container) -> bool
             {
                 $(
-                    container.contains(TypeId::of::<$i::Component>())
+                    $i::matches(container)
                 )&&*
             }
 
             fn locks() -> Vec<Lock> {
-                vec![
+                [
                     $(
                         $i::lock(),
                     )*
                 ]
+                .into_iter()
+                .flatten()
+                .collect()
             }
         }
 
         #[allow(non_snake_case)]
         impl<'w, $($i),*> Iterator for Zipper<'w, ($($i,)*)>
         where
-            $($i: Iterator + 'w,)*
+            $($i: Iterator + 'w, $i::Item: Row,)*
         {
-            type Item = ($($i::Item,)*);
+            type Item = ($(<$i::Item as Row>::Value,)*);
 
             fn next(&mut self) -> Option<Self::Item> {
                 let ($(ref mut $i,)*) = self.tuple;
-                $(
-                    let $i = match $i.next() {
-                        None => return None,
-                        Some(item) => item,
-                    };
-
-                )*
-                Some(($($i,)*))
+                loop {
+                    // all iterators are advanced to keep columns aligned
+                    $(
+                        let $i = $i.next()?;
+                    )*
+                    // values are taken only for selected entities
+                    if $($i.is_selected())&&* {
+                        return Some(($($i.value()?,)*));
+                    }
+                }
             }
         }
     }
 }
 recursive!(impl_queries, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
 
-/// Iterator or Query result
-pub struct QueryIter<I>
-where
-    I: Iterator,
-{
-    iter: I,
+/// Components of the entity, picked by [`World::get`]
+pub struct Pick<'w, Q> {
+    value: Q,
+    _guard: LockGuard,
+    _lifetime: PhantomData<&'w ()>,
+}
+
+impl<'w, Q> std::ops::Deref for Pick<'w, Q> {
+    type Target = Q;
+
+    fn deref(&self) -> &Q {
+        &self.value
+    }
+}
+
+impl<'w, Q> std::ops::DerefMut for Pick<'w, Q> {
+    fn deref_mut(&mut self) -> &mut Q {
+        &mut self.value
+    }
+}
+
+/// Holds query or resource locks until dropped
+struct LockGuard {
     locks: Vec<Lock>,
     lock_manager: Arc<(Mutex<TypeLock>, Condvar)>,
 }
 
-impl<I> Drop for QueryIter<I>
-where
-    I: Iterator,
-{
+impl Drop for LockGuard {
     fn drop(&mut self) {
         let (mutex, cvar) = &*self.lock_manager;
 
@@ -311,6 +728,15 @@ where
     }
 }
 
+/// Iterator or Query result
+pub struct QueryIter<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    _guard: LockGuard,
+}
+
 impl<I> Iterator for QueryIter<I>
 where
     I: Iterator,
@@ -343,35 +769,27 @@ where
 {
     type Item = Id<Entity>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.entries.next().map(|entry| {
-            let id = Id::<Entity>::new();
-            let volatile = T::volatile();
-            let entity = entry.entity().with(id);
-            self.container_index = self
-                .container_index
-                // container was previously set
-                .and_then(|i| {
-                    if volatile && !self.world.content[i].matches(&mut entity.archetype()) {
-                        // we may need different container for the entity
-                        return None;
-                    }
-                    Some(i)
-                })
-                .or_else(|| self.world.find_container_for_entity(&entity))
-                .or_else(|| Some(self.world.create_container_for_entity(&entity)));
-
-            let index = {
-                let container_index = self.container_index.unwrap();
-                let container = &mut self.world.content[container_index];
-                let address = container.store(entity);
-                Index {
-                    container: container_index,
-                    address,
-                }
-            };
-            self.world.index.insert(id, index);
-            id
-        })
+        let entry = self.entries.next()?;
+        let id = Id::<Entity>::new();
+        if T::volatile() {
+            self.world.spawn_with_id(id, entry.entity());
+            return Some(id);
+        }
+        // entries of a fixed set of components share the container
+        if self.container_index.is_none() {
+            self.container_index =
+                T::components().map(|archetype| self.world.container(&archetype));
+        }
+        match self.container_index {
+            Some(container) => {
+                let tick = self.world.change_tick();
+                let address = entry.store(&mut self.world.content[container], id, tick);
+                self.world.allocate(id, Index { container, address });
+                self.world.emit(WorldEvent::Spawned(id));
+            }
+            None => self.world.spawn_with_id(id, entry.entity()),
+        }
+        Some(id)
     }
     fn count(self) -> usize {
         let mut result = 0;
@@ -393,7 +811,12 @@ unsafe impl Sync for World {}
 
 #[cfg(test)]
 mod tests {
-    use super::World;
+    use std::collections::HashMap;
+
+    use proptest::prelude::*;
+
+    use super::{Added, Changed, Entity, With, Without, World};
+    use crate::utils::Id;
 
     #[derive(Debug, Eq, PartialEq, Copy, Clone)]
     struct Armor(u32);
@@ -460,6 +883,344 @@ mod tests {
             assert_eq!(weight.0, 5000);
         }
     }
+
+    #[test]
+    fn can_execute_in_parallel() {
+        let mut world = spawn();
+        world
+            .spawn((0..10_000).map(|i| (SpeedComponent(i), WeightComponent(1))))
+            .count();
+
+        world.execute_chunked::<(&SpeedComponent, &mut WeightComponent), _>(
+            64,
+            |(speed, weight)| {
+                weight.0 += speed.0;
+            },
+        );
+        world.execute_parallel::<(&mut HealthComponent,), _>(|(health,)| {
+            health.0 += 1;
+        });
+
+        let total: u64 = world
+            .query::<(&WeightComponent,)>()
+            .map(|(weight,)| weight.0 as u64)
+            .sum();
+        let expected = 9 * (5000 + 35) + (0..10_000).map(|i| i + 1).sum::<u64>();
+        assert_eq!(total, expected);
+
+        let health = world
+            .query::<(&HealthComponent,)>()
+            .map(|(health,)| health.0)
+            .collect::<Vec<_>>();
+        assert_eq!(health, vec![101, 81]);
+    }
+
+    #[test]
+    fn can_insert_and_remove_components() {
+        let mut world = spawn();
+        let ids = world
+            .spawn([
+                (SpeedComponent(1), WeightComponent(10)),
+                (SpeedComponent(2), WeightComponent(20)),
+            ])
+            .collect::<Vec<_>>();
+
+        assert!(world.insert(&ids[0], Armor(7)));
+        assert!(world.insert(&ids[1], WeightComponent(25)));
+        assert!(!world.insert(&Id::new(), Armor(1)));
+
+        let migrated = world
+            .get::<(&Armor, &WeightComponent)>(&ids[0])
+            .expect("entity should be migrated");
+        let (armor, weight) = &*migrated;
+        assert_eq!((armor.0, weight.0), (7, 10));
+        drop(migrated);
+        assert_eq!(world.get::<(&WeightComponent,)>(&ids[1]).unwrap().0 .0, 25);
+
+        // entity with the same archetype is stored in the same container
+        let armored = world
+            .query::<(&Id<Entity>, &Armor, &SpeedComponent)>()
+            .map(|(id, armor, speed)| (*id, armor.0, speed.0))
+            .collect::<Vec<_>>();
+        assert_eq!(armored, vec![(ids[0], 7, 1)]);
+
+        assert_eq!(
+            world.remove::<SpeedComponent>(&ids[0]).map(|s| s.0),
+            Some(1)
+        );
+        assert!(world.remove::<SpeedComponent>(&ids[0]).is_none());
+        assert!(world.remove::<Id<Entity>>(&ids[0]).is_none());
+
+        let picked = world
+            .get::<(&Id<Entity>, &Armor, &WeightComponent)>(&ids[0])
+            .unwrap();
+        let (id, armor, weight) = &*picked;
+        assert_eq!((**id, armor.0, weight.0), (ids[0], 7, 10));
+        drop(picked);
+
+        let speeds = world
+            .query::<(&SpeedComponent, &WeightComponent)>()
+            .map(|(speed, weight)| (speed.0, weight.0))
+            .collect::<Vec<_>>();
+        assert_eq!(speeds.len(), 10);
+        assert!(speeds.contains(&(2, 25)));
+        assert!(!speeds.contains(&(1, 10)));
+    }
+
+    #[test]
+    fn can_filter_queries() {
+        let world = spawn();
+
+        let mut damage = world
+            .query::<(&DamageComponent, With<Armor>)>()
+            .map(|(damage, _)| damage.0)
+            .collect::<Vec<_>>();
+        damage.sort();
+        assert_eq!(damage, vec![300, 600]);
+
+        let damage = world
+            .query::<(&DamageComponent, Without<Armor>)>()
+            .map(|(damage, _)| damage.0)
+            .collect::<Vec<_>>();
+        assert_eq!(damage, vec![45]);
+
+        let mut health = world
+            .query::<(&Armor, Option<&HealthComponent>)>()
+            .map(|(armor, health)| (armor.0, health.map(|health| health.0)))
+            .collect::<Vec<_>>();
+        health.sort();
+        assert_eq!(health, vec![(10, None), (100, Some(100))]);
+    }
+
+    #[test]
+    fn can_detect_changes() {
+        let mut world = spawn();
+        let since = world.advance_tick();
+        assert_eq!(world.query_since::<(Changed<Armor>,)>(since).count(), 0);
+
+        let id = world.spawn([(Armor(1),)]).next().unwrap();
+        for (armor, _) in world.query::<(&mut Armor, With<DamageComponent>)>() {
+            if armor.0 == 10 {
+                armor.0 = 11;
+            }
+        }
+
+        let mut changed = world
+            .query_since::<(&Armor, Changed<Armor>)>(since)
+            .map(|(armor, _)| armor.0)
+            .collect::<Vec<_>>();
+        changed.sort();
+        assert_eq!(changed, vec![1, 11, 100]);
+
+        let added = world
+            .query_since::<(&Id<Entity>, Added<Armor>)>(since)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        assert_eq!(added, vec![id]);
+        assert!(world.get::<(Added<Armor>,)>(&id).is_some());
+
+        // ticks are kept when the entity migrates to another container
+        let since = world.advance_tick();
+        world.insert(&id, HealthComponent(1));
+        assert!(world.query_since::<(Added<Armor>,)>(since).next().is_none());
+        assert_eq!(
+            world
+                .query_since::<(&HealthComponent, Added<HealthComponent>)>(since)
+                .count(),
+            1
+        );
+        assert_eq!(world.query::<(Changed<Armor>,)>().count(), 3);
+    }
+
+    #[test]
+    fn picked_components_are_unlocked() {
+        let world = spawn();
+        let id = *world.query::<(&Id<Entity>, &Armor)>().next().unwrap().0;
+        {
+            let mut picked = world.get::<(&mut Armor,)>(&id).unwrap();
+            picked.0 .0 += 1;
+        }
+        assert!(world.get::<(&Armor,)>(&id).is_some());
+        world.execute::<(&mut Armor,), _>(|(armor,)| armor.0 += 1);
+        assert!(world.get::<(&mut Armor,)>(&id).is_some());
+        let armor = world.query::<(&Armor,)>().map(|(armor,)| armor.0).max();
+        assert_eq!(armor, Some(102));
+    }
+
+    #[test]
+    fn filtered_out_entities_are_not_changed() {
+        let mut world = World::new();
+        let ids = world.spawn([(Armor(1),), (Armor(2),)]).collect::<Vec<_>>();
+        world.spawn([(Armor(3), HealthComponent(3))]).count();
+        let since = world.advance_tick();
+        let changed = |world: &World| {
+            let mut changed = world
+                .query_since::<(&Armor, Changed<Armor>)>(since)
+                .map(|(armor, _)| armor.0)
+                .collect::<Vec<_>>();
+            changed.sort();
+            changed
+        };
+
+        world.component_mut::<Armor>(&ids[0]).unwrap().0 = 10;
+        let selected = world
+            .query_since::<(&mut Armor, Changed<Armor>)>(since)
+            .map(|(armor, _)| armor.0)
+            .collect::<Vec<_>>();
+        assert_eq!(selected, vec![10]);
+        let selected = world
+            .query_since::<(Changed<Armor>, &mut Armor)>(since)
+            .map(|(_, armor)| armor.0)
+            .collect::<Vec<_>>();
+        assert_eq!(selected, vec![10]);
+        assert!(world
+            .query_since::<(&mut Armor, Changed<Armor>)>(since)
+            .all(|(armor, _)| armor.0 == 10));
+        assert_eq!(changed(&world), vec![10]);
+
+        for (armor, _) in world.query::<(&mut Armor, With<HealthComponent>)>() {
+            armor.0 += 1;
+        }
+        assert_eq!(changed(&world), vec![4, 10]);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        Spawn(Option<u32>, Option<u32>),
+        Exile(usize),
+        InsertArmor(usize, u32),
+        RemoveHealth(usize),
+        ReplaceId(usize),
+    }
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        prop_oneof![
+            (any::<Option<u32>>(), any::<Option<u32>>())
+                .prop_map(|(armor, health)| Operation::Spawn(armor, health)),
+            any::<usize>().prop_map(Operation::Exile),
+            (any::<usize>(), any::<u32>())
+                .prop_map(|(entity, armor)| Operation::InsertArmor(entity, armor)),
+            any::<usize>().prop_map(Operation::RemoveHealth),
+            any::<usize>().prop_map(Operation::ReplaceId),
+        ]
+    }
+
+    /// Expected components of an entity
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    struct Model {
+        tag: u32,
+        armor: Option<u32>,
+        health: Option<u32>,
+    }
+
+    fn apply(
+        world: &mut World,
+        spawned: &mut Vec<Id<Entity>>,
+        models: &mut HashMap<Id<Entity>, Model>,
+        operation: Operation,
+    ) {
+        let pick = |index: usize| spawned.get(index % spawned.len().max(1)).copied();
+        match operation {
+            Operation::Spawn(armor, health) => {
+                let tag = SpeedComponent(spawned.len() as u32);
+                let id = match (armor, health) {
+                    (Some(a), Some(h)) => world.spawn([(tag, Armor(a), HealthComponent(h))]).next(),
+                    (Some(a), None) => world.spawn([(tag, Armor(a))]).next(),
+                    (None, Some(h)) => world.spawn([(tag, HealthComponent(h))]).next(),
+                    (None, None) => world.spawn([(tag,)]).next(),
+                }
+                .unwrap();
+                let tag = spawned.len() as u32;
+                spawned.push(id);
+                models.insert(id, Model { tag, armor, health });
+            }
+            Operation::Exile(index) => {
+                if let Some(id) = pick(index) {
+                    let handle = world.handle(&id);
+                    let exiled = world.exile(&id);
+                    assert_eq!(exiled.is_some(), models.remove(&id).is_some());
+                    assert!(handle.map(|handle| !world.contains(handle)).unwrap_or(true));
+                }
+            }
+            Operation::InsertArmor(index, armor) => {
+                if let Some(id) = pick(index) {
+                    let inserted = world.insert(&id, Armor(armor));
+                    let model = models.get_mut(&id);
+                    assert_eq!(inserted, model.is_some());
+                    if let Some(model) = model {
+                        model.armor = Some(armor);
+                    }
+                }
+            }
+            Operation::RemoveHealth(index) => {
+                if let Some(id) = pick(index) {
+                    let removed = world.remove::<HealthComponent>(&id).map(|h| h.0);
+                    let expected = models.get_mut(&id).and_then(|model| model.health.take());
+                    assert_eq!(removed, expected);
+                }
+            }
+            Operation::ReplaceId(index) => {
+                if let Some(id) = pick(index) {
+                    assert!(!world.insert(&id, Id::<Entity>::new()));
+                    assert!(world.component_mut::<Id<Entity>>(&id).is_none());
+                }
+            }
+        }
+    }
+
+    fn check(world: &World, spawned: &[Id<Entity>], models: &HashMap<Id<Entity>, Model>) {
+        for id in spawned.iter() {
+            let model = models.get(id);
+            let handle = world.handle(id);
+            assert_eq!(handle.is_some(), model.is_some());
+            assert_eq!(
+                handle.and_then(|handle| world.id(handle)),
+                model.map(|_| *id)
+            );
+            let tag = world
+                .get::<(&Id<Entity>, &SpeedComponent)>(id)
+                .map(|picked| {
+                    let (entity_id, tag) = *picked;
+                    assert_eq!(entity_id, id);
+                    tag.0
+                });
+            assert_eq!(tag, model.map(|model| model.tag));
+            let armor = world.get::<(&Armor,)>(id).map(|picked| picked.0 .0);
+            assert_eq!(armor, model.and_then(|model| model.armor));
+            let health = world
+                .get::<(&HealthComponent,)>(id)
+                .map(|picked| picked.0 .0);
+            assert_eq!(health, model.and_then(|model| model.health));
+        }
+
+        let mut queried = world
+            .query::<(&Id<Entity>, &SpeedComponent, &Armor)>()
+            .map(|(id, tag, armor)| (*id, tag.0, armor.0))
+            .collect::<Vec<_>>();
+        let mut expected = models
+            .iter()
+            .filter_map(|(id, model)| model.armor.map(|armor| (*id, model.tag, armor)))
+            .collect::<Vec<_>>();
+        queried.sort_by_key(|(_, tag, _)| *tag);
+        expected.sort_by_key(|(_, tag, _)| *tag);
+        assert_eq!(queried, expected);
+
+        let count = world.query::<(&Id<Entity>,)>().count();
+        assert_eq!(count, models.len());
+    }
+
+    proptest! {
+        #[test]
+        fn world_is_consistent(operations in prop::collection::vec(operation(), 1..64)) {
+            let mut world = World::new();
+            let mut spawned = Vec::new();
+            let mut models = HashMap::new();
+            for operation in operations {
+                apply(&mut world, &mut spawned, &mut models, operation);
+                check(&world, &spawned, &models);
+            }
+        }
+    }
 }
 
 /*
@@ -0,0 +1,144 @@
+//! Deferred mutations of the world
+use std::any::Any;
+
+use super::{Entity, IntoEntity, World};
+use crate::log;
+use crate::tasks::{All, Mut, Take, Task};
+use crate::utils::Id;
+
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Buffer of [`World`] mutations, applied by the [`ApplyCommands`] task
+///
+/// Tasks, holding only `Ref<World>`, return it as an output. Commands of every buffer are
+/// applied in order, but order of buffers from different tasks is not defined.
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    /// Constructs an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns the entity and returns ID, it will get
+    pub fn spawn<T>(&mut self, entry: T) -> Id<Entity>
+    where
+        T: IntoEntity + Send + 'static,
+    {
+        let id = Id::new();
+        self.add(move |world| world.spawn_with_id(id, entry.entity()));
+        id
+    }
+
+    /// Exiles the entity
+    pub fn exile(&mut self, id: Id<Entity>) {
+        self.add(move |world| {
+            world.exile(&id);
+        });
+    }
+
+    /// Inserts the component into the entity, replacing the existing one of the same type
+    ///
+    /// The `Id<Entity>` component can not be replaced, see [`World::insert`].
+    pub fn insert<C: Any + Send>(&mut self, id: Id<Entity>, component: C) {
+        self.add(move |world| {
+            if !world.insert(&id, component) {
+                log::warn!("Could not insert component into {:?}", id);
+            }
+        });
+    }
+
+    /// Removes the component from the entity
+    pub fn remove<C: Any>(&mut self, id: Id<Entity>) {
+        self.add(move |world| {
+            world.remove::<C>(&id);
+        });
+    }
+
+    /// Adds a custom command
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.queue.push(Box::new(command));
+    }
+
+    /// Returns number of commands in the buffer
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns true if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Applies commands to the world and returns their number
+    pub fn apply(self, world: &mut World) -> usize {
+        let count = self.queue.len();
+        for command in self.queue {
+            command(world);
+        }
+        count
+    }
+}
+
+/// Output of the [`ApplyCommands`] task
+pub struct CommandsApplied {
+    /// Number of applied commands
+    pub count: usize,
+}
+
+/// Task, applying [`Commands`] of all tasks to the [`World`]
+///
+/// Runs once all tasks, providing [`Commands`], are completed.
+#[derive(Default)]
+pub struct ApplyCommands {}
+
+impl Task for ApplyCommands {
+    type Context = (Take<All<Commands>>, Mut<World>);
+    type Output = CommandsApplied;
+
+    fn run(&mut self, (mut commands, mut world): Self::Context) -> Self::Output {
+        let count = commands
+            .drain()
+            .map(|commands| commands.apply(&mut world))
+            .sum();
+        log::debug!("ApplyCommands: {} commands applied", count);
+        CommandsApplied { count }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Commands;
+    use crate::world::World;
+
+    #[test]
+    fn commands_are_deferred() {
+        let mut world = World::new();
+        let existing = world.spawn([(1_u32,)]).next().unwrap();
+
+        let mut commands = Commands::new();
+        let spawned = commands.spawn((2_u32, 'a'));
+        commands.insert(existing, 'b');
+        commands.remove::<u32>(spawned);
+        commands.add(move |world| {
+            world.insert(&spawned, 3.0_f32);
+        });
+        assert_eq!(commands.len(), 4);
+        assert!(!world.contains(&spawned));
+
+        assert_eq!(commands.apply(&mut world), 4);
+        assert_eq!(world.component_mut::<char>(&existing), Some(&mut 'b'));
+        assert_eq!(world.component_mut::<u32>(&spawned), None);
+        assert_eq!(world.component_mut::<char>(&spawned), Some(&mut 'a'));
+        assert_eq!(world.component_mut::<f32>(&spawned), Some(&mut 3.0));
+
+        let mut commands = Commands::new();
+        commands.exile(existing);
+        commands.apply(&mut world);
+        assert!(!world.contains(&existing));
+        assert!(world.contains(&spawned));
+    }
+}
@@ -0,0 +1,131 @@
+//! Lifecycle events of entities
+use std::any::{Any, TypeId};
+
+use super::{Entity, World};
+use crate::log;
+use crate::tasks::{Ref, Task};
+use crate::utils::Id;
+
+/// Lifecycle event of an entity
+///
+/// Events are recorded only after [`World::observe`] was called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldEvent {
+    /// Entity was spawned
+    Spawned(Id<Entity>),
+    /// Entity was exiled
+    Exiled(Id<Entity>),
+    /// Component was added to the existing entity
+    ComponentAdded {
+        /// ID of the entity
+        entity: Id<Entity>,
+        /// Type of the component
+        component: TypeId,
+    },
+    /// Component was removed from the existing entity
+    ComponentRemoved {
+        /// ID of the entity
+        entity: Id<Entity>,
+        /// Type of the component
+        component: TypeId,
+    },
+}
+
+impl WorldEvent {
+    /// Returns ID of the entity
+    pub fn entity(&self) -> Id<Entity> {
+        match self {
+            WorldEvent::Spawned(entity) | WorldEvent::Exiled(entity) => *entity,
+            WorldEvent::ComponentAdded { entity, .. }
+            | WorldEvent::ComponentRemoved { entity, .. } => *entity,
+        }
+    }
+
+    /// Returns true if the event is addition of the component of type `C`
+    pub fn is_added<C: Any>(&self) -> bool {
+        matches!(self, WorldEvent::ComponentAdded { component, .. } if *component == TypeId::of::<C>())
+    }
+
+    /// Returns true if the event is removal of the component of type `C`
+    pub fn is_removed<C: Any>(&self) -> bool {
+        matches!(self, WorldEvent::ComponentRemoved { component, .. } if *component == TypeId::of::<C>())
+    }
+}
+
+/// Output of the [`ObserveWorld`] task
+#[derive(Debug, Default, Clone)]
+pub struct WorldEvents {
+    /// Events in order of their occurrence
+    pub events: Vec<WorldEvent>,
+}
+
+/// Task, providing events recorded by the [`World`] since its previous run
+///
+/// Observation starts on the first run, so to get events of entities spawned on startup,
+/// call [`World::observe`] before spawning them.
+#[derive(Default)]
+pub struct ObserveWorld {}
+
+impl Task for ObserveWorld {
+    type Context = (Ref<World>,);
+    type Output = WorldEvents;
+
+    fn run(&mut self, (world,): Self::Context) -> Self::Output {
+        world.observe();
+        let events = world.take_events();
+        log::debug!("ObserveWorld: {} events", events.len());
+        WorldEvents { events }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+
+    use super::WorldEvent;
+    use crate::world::World;
+
+    #[test]
+    fn lifecycle_events_are_recorded() {
+        let mut world = World::new();
+        let first = world.spawn([(1_u32,)]).next().unwrap();
+        world.observe();
+        assert!(world.take_events().is_empty());
+
+        let ids = world.spawn([(2_u32,), (3_u32,)]).collect::<Vec<_>>();
+        assert!(world.insert(&ids[0], 'a'));
+        // replacement of the component is a change, not an addition
+        assert!(world.insert(&ids[0], 'b'));
+        assert_eq!(world.remove::<u32>(&ids[0]), Some(2));
+        assert_eq!(world.remove::<u32>(&ids[0]), None);
+        world.exile(&ids[1]);
+
+        let events = world.take_events();
+        assert_eq!(
+            events,
+            vec![
+                WorldEvent::Spawned(ids[0]),
+                WorldEvent::Spawned(ids[1]),
+                WorldEvent::ComponentAdded {
+                    entity: ids[0],
+                    component: TypeId::of::<char>(),
+                },
+                WorldEvent::ComponentRemoved {
+                    entity: ids[0],
+                    component: TypeId::of::<u32>(),
+                },
+                WorldEvent::Exiled(ids[1]),
+            ]
+        );
+        assert!(events[2].is_added::<char>());
+        assert!(events[3].is_removed::<u32>());
+        assert_eq!(events[3].entity(), ids[0]);
+
+        world.clear();
+        let mut exiled = world.take_events();
+        exiled.sort_by_key(|event| *event.entity().uuid());
+        let mut expected = vec![WorldEvent::Exiled(first), WorldEvent::Exiled(ids[0])];
+        expected.sort_by_key(|event| *event.entity().uuid());
+        assert_eq!(exiled, expected);
+    }
+}
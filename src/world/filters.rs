@@ -0,0 +1,240 @@
+//! Query filters and optional components
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use super::storage::{self, ComponentTicks, Container};
+use super::{QueryTicks, Row, Selector};
+use crate::utils::Lock;
+
+/// Filter of entities having the component, the component itself is not borrowed
+pub struct With<C>(PhantomData<C>);
+
+/// Filter of entities without the component
+pub struct Without<C>(PhantomData<C>);
+
+/// Filter of entities, which component was mutably borrowed after the reference tick
+///
+/// Mutable borrow is considered as a change even if the component was not modified.
+pub struct Changed<C>(PhantomData<C>);
+
+/// Filter of entities, which component was added after the reference tick
+pub struct Added<C>(PhantomData<C>);
+
+/// Iterator yielding the filter for every slot of the range
+pub type FilterIter<F> = std::iter::Map<Range<usize>, fn(usize) -> Option<F>>;
+
+/// Returns range of slots clamped to the container size
+fn clamp(container: &Container, range: Range<usize>) -> Range<usize> {
+    let slots = container.slots();
+    range.start.min(slots)..range.end.min(slots)
+}
+
+impl<'w, C> Selector<'w> for With<C>
+where
+    C: Send + Sync + 'static,
+{
+    type Row = Option<Self>;
+    type Iter = FilterIter<Self>;
+
+    fn borrow(container: &'w Container, range: Range<usize>, _: QueryTicks) -> Self::Iter {
+        clamp(container, range).map(|_| Some(With(PhantomData)))
+    }
+
+    fn borrow_one(_: &'w Container, _: usize, _: QueryTicks) -> Self::Row {
+        Some(With(PhantomData))
+    }
+
+    fn matches(container: &'w Container) -> bool {
+        container.contains(TypeId::of::<C>())
+    }
+
+    fn lock() -> Option<Lock> {
+        None
+    }
+}
+
+impl<'w, C> Selector<'w> for Without<C>
+where
+    C: Send + Sync + 'static,
+{
+    type Row = Option<Self>;
+    type Iter = FilterIter<Self>;
+
+    fn borrow(container: &'w Container, range: Range<usize>, _: QueryTicks) -> Self::Iter {
+        clamp(container, range).map(|_| Some(Without(PhantomData)))
+    }
+
+    fn borrow_one(_: &'w Container, _: usize, _: QueryTicks) -> Self::Row {
+        Some(Without(PhantomData))
+    }
+
+    fn matches(container: &'w Container) -> bool {
+        !container.contains(TypeId::of::<C>())
+    }
+
+    fn lock() -> Option<Lock> {
+        None
+    }
+}
+
+/// Iterator over change ticks of the component, yielding the filter for matching slots
+pub struct TicksFilterIter<'w, F> {
+    ticks: storage::TicksIter<'w>,
+    since: u64,
+    tick: fn(&ComponentTicks) -> u64,
+    filter: fn() -> F,
+}
+
+impl<'w, F> Iterator for TicksFilterIter<'w, F> {
+    type Item = Option<F>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ticks
+            .next()
+            .map(|ticks| ((self.tick)(&ticks) > self.since).then(self.filter))
+    }
+}
+
+impl<'w, C> Selector<'w> for Changed<C>
+where
+    C: Send + Sync + 'static,
+{
+    type Row = Option<Self>;
+    type Iter = TicksFilterIter<'w, Self>;
+
+    fn borrow(container: &'w Container, range: Range<usize>, ticks: QueryTicks) -> Self::Iter {
+        TicksFilterIter {
+            ticks: container.ticks_range::<C>(range),
+            since: ticks.since,
+            tick: |ticks| ticks.changed,
+            filter: || Changed(PhantomData),
+        }
+    }
+
+    fn borrow_one(container: &'w Container, entity_index: usize, ticks: QueryTicks) -> Self::Row {
+        container
+            .ticks::<C>(entity_index)
+            .filter(|component_ticks| component_ticks.changed > ticks.since)
+            .map(|_| Changed(PhantomData))
+    }
+
+    fn matches(container: &'w Container) -> bool {
+        container.contains(TypeId::of::<C>())
+    }
+
+    fn lock() -> Option<Lock> {
+        None
+    }
+}
+
+impl<'w, C> Selector<'w> for Added<C>
+where
+    C: Send + Sync + 'static,
+{
+    type Row = Option<Self>;
+    type Iter = TicksFilterIter<'w, Self>;
+
+    fn borrow(container: &'w Container, range: Range<usize>, ticks: QueryTicks) -> Self::Iter {
+        TicksFilterIter {
+            ticks: container.ticks_range::<C>(range),
+            since: ticks.since,
+            tick: |ticks| ticks.added,
+            filter: || Added(PhantomData),
+        }
+    }
+
+    fn borrow_one(container: &'w Container, entity_index: usize, ticks: QueryTicks) -> Self::Row {
+        container
+            .ticks::<C>(entity_index)
+            .filter(|component_ticks| component_ticks.added > ticks.since)
+            .map(|_| Added(PhantomData))
+    }
+
+    fn matches(container: &'w Container) -> bool {
+        container.contains(TypeId::of::<C>())
+    }
+
+    fn lock() -> Option<Lock> {
+        None
+    }
+}
+
+/// Iterator over optional components
+pub enum OptionIter<I> {
+    /// Container has the component
+    Some(I),
+    /// Container has no component, so `None` is yielded for every slot of the range
+    None(Range<usize>),
+}
+
+impl<I> Iterator for OptionIter<I>
+where
+    I: Iterator,
+{
+    type Item = OptionRow<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            OptionIter::Some(iter) => iter.next().map(|row| OptionRow(Some(row))),
+            OptionIter::None(range) => range.next().map(|_| OptionRow(None)),
+        }
+    }
+}
+
+/// Row of the optional selector, `None` if the container has no component
+pub struct OptionRow<R>(Option<R>);
+
+impl<R: Row> Row for OptionRow<R> {
+    type Value = Option<R::Value>;
+
+    fn is_selected(&self) -> bool {
+        self.0.as_ref().map(|row| row.is_selected()).unwrap_or(true)
+    }
+
+    fn value(self) -> Option<Self::Value> {
+        match self.0 {
+            Some(row) => row.value().map(Some),
+            None => Some(None),
+        }
+    }
+}
+
+impl<'w, S> Selector<'w> for Option<S>
+where
+    S: Selector<'w> + OptionalSelector,
+{
+    type Row = OptionRow<S::Row>;
+    type Iter = OptionIter<S::Iter>;
+
+    fn borrow(container: &'w Container, range: Range<usize>, ticks: QueryTicks) -> Self::Iter {
+        if S::matches(container) {
+            OptionIter::Some(S::borrow(container, range, ticks))
+        } else {
+            OptionIter::None(clamp(container, range))
+        }
+    }
+
+    fn borrow_one(container: &'w Container, entity_index: usize, ticks: QueryTicks) -> Self::Row {
+        if S::matches(container) {
+            OptionRow(Some(S::borrow_one(container, entity_index, ticks)))
+        } else {
+            OptionRow(None)
+        }
+    }
+
+    fn matches(_: &'w Container) -> bool {
+        true
+    }
+
+    fn lock() -> Option<Lock> {
+        S::lock()
+    }
+}
+
+/// Selectors, which can be optional: `Option<&C>` and `Option<&mut C>`
+pub trait OptionalSelector {}
+
+impl<C> OptionalSelector for &C {}
+
+impl<C> OptionalSelector for &mut C {}
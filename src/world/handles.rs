@@ -0,0 +1,149 @@
+//! Generational handles of entities
+use super::{Entity, Index, World};
+use crate::utils::Id;
+
+/// Generational handle of the entity
+///
+/// Unlike `Id<Entity>`, the handle is resolved without hashing, but it is meaningful only for
+/// the world, that issued it. The handle becomes stale, when the entity is exiled, even if its
+/// slot is reused by another entity. Use `Id<Entity>` for persistence and networking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+impl Handle {
+    /// Returns index of the entity slot
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns generation of the entity slot
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Key to access the entity in the [`World`]: `&Id<Entity>` or [`Handle`]
+pub trait EntityKey: Copy {
+    /// Returns slot of the entity, if it exists
+    fn slot(self, world: &World) -> Option<usize>;
+}
+
+impl EntityKey for &Id<Entity> {
+    fn slot(self, world: &World) -> Option<usize> {
+        world.index.get(self).map(|slot| *slot as usize)
+    }
+}
+
+impl EntityKey for Handle {
+    fn slot(self, world: &World) -> Option<usize> {
+        let index = self.index as usize;
+        world
+            .slots
+            .get(index)
+            .filter(|slot| slot.generation == self.generation && slot.entry.is_some())
+            .map(|_| index)
+    }
+}
+
+/// Slot of the entity, reused after the entity is exiled
+#[derive(Default)]
+pub(super) struct Slot {
+    pub(super) generation: u32,
+    pub(super) entry: Option<Entry>,
+}
+
+/// Entity, occupying the slot
+pub(super) struct Entry {
+    pub(super) id: Id<Entity>,
+    pub(super) index: Index,
+}
+
+impl World {
+    /// Returns handle of the entity
+    pub fn handle(&self, id: &Id<Entity>) -> Option<Handle> {
+        let slot = id.slot(self)?;
+        Some(Handle {
+            index: slot as u32,
+            generation: self.slots[slot].generation,
+        })
+    }
+
+    /// Returns ID of the entity
+    pub fn id(&self, key: impl EntityKey) -> Option<Id<Entity>> {
+        key.slot(self).map(|slot| self.entry(slot).id)
+    }
+
+    /// Occupies a free slot by the entity and returns the slot
+    pub(super) fn allocate(&mut self, id: Id<Entity>, index: Index) -> usize {
+        let slot = match self.free.pop() {
+            Some(slot) => slot as usize,
+            None => {
+                self.slots.push(Slot::default());
+                self.slots.len() - 1
+            }
+        };
+        self.slots[slot].entry = Some(Entry { id, index });
+        self.index.insert(id, slot as u32);
+        slot
+    }
+
+    /// Frees the slot and returns ID of the entity, that occupied it
+    pub(super) fn release(&mut self, slot: usize) -> Id<Entity> {
+        let entry = self.slots[slot]
+            .entry
+            .take()
+            .expect("Slot should be occupied");
+        // handles of the exiled entity become stale
+        self.slots[slot].generation = self.slots[slot].generation.wrapping_add(1);
+        self.free.push(slot as u32);
+        self.index.remove(&entry.id);
+        entry.id
+    }
+
+    /// Returns entity, occupying the slot
+    pub(super) fn entry(&self, slot: usize) -> &Entry {
+        self.slots[slot]
+            .entry
+            .as_ref()
+            .expect("Slot should be occupied")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::World;
+
+    #[test]
+    fn handles_become_stale() {
+        let mut world = World::new();
+        let ids = world.spawn([(1_u32,), (2_u32,)]).collect::<Vec<_>>();
+        let handles = ids
+            .iter()
+            .map(|id| world.handle(id).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(world.id(handles[1]), Some(ids[1]));
+
+        // swap-remove keeps handles of moved entities valid
+        assert!(world.exile(handles[0]).is_some());
+        assert!(!world.contains(handles[0]));
+        assert_eq!(world.component_mut::<u32>(handles[1]), Some(&mut 2));
+
+        // the slot is reused, but the old handle does not resolve to the new entity
+        let id = world.spawn([(3_u32,)]).next().unwrap();
+        let handle = world.handle(&id).unwrap();
+        assert_eq!(handle.index(), handles[0].index());
+        assert_ne!(handle.generation(), handles[0].generation());
+        assert_eq!(world.component_mut::<u32>(handles[0]), None);
+        assert!(world.insert(handle, 'a'));
+        assert_eq!(world.component_mut::<char>(handle), Some(&mut 'a'));
+
+        world.clear();
+        assert!(!world.contains(handle));
+        let id = world.spawn([(4_u32,)]).next().unwrap();
+        assert!(!world.contains(handle));
+        assert!(world.contains(world.handle(&id).unwrap()));
+    }
+}
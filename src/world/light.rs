@@ -1,19 +1,22 @@
-use std::{ops::Range, task::Context};
+//! Light module
 
-use dotrix_core as dotrix;
-use dotrix_ecs::World;
-use dotrix_gpu::{Buffer, Gpu, Texture, TextureView};
-use dotrix_math::{Deg, InnerSpace, Rad, Vec3};
-use dotrix_types::{Camera, Color, Id};
+use std::ops::Range;
 
+use crate::math::Vec3;
+use crate::models::Color;
+
+/// Position of the light source
 #[derive(Debug, Clone)]
 pub enum Position {
     /// Directional ambient light
     Ambient { dir_x: f32, dir_y: f32, dir_z: f32 },
     /// Light with a source at some defined point
     Point { x: f32, y: f32, z: f32 },
+    /// Light with a source at some defined point, streaming in the defined direction
+    Spot { x: f32, y: f32, z: f32 },
 }
 
+/// Light component
 #[derive(Debug, Clone)]
 pub struct Light {
     /// The color of light, alpha channel is being used as intensity
@@ -22,8 +25,8 @@ pub struct Light {
     pub position: Position,
     /// Light Stream Direction vector
     pub stream: Vec3,
-    /// Field of View
-    pub fov: Rad<f32>,
+    /// Field of View (rad)
+    pub fov: f32,
     /// Depth of light
     pub depth: Range<f32>,
     /// Light source constant attenuation
@@ -32,9 +35,9 @@ pub struct Light {
     pub blur_linear: f32,
     /// Light source quadratic attenuation
     pub blur_quadratic: f32,
-    /// Light ray cut off
+    /// Light ray cut off (rad)
     pub cut_off_inner: f32,
-    /// Light ray outer cut off
+    /// Light ray outer cut off (rad)
     pub cut_off_outer: f32,
     /// Light On/Off toggle
     pub enabled: bool,
@@ -52,7 +55,7 @@ impl Default for Light {
                 dir_z: -2.0,
             },
             stream: Vec3::new(0.0, 0.0, 0.0),
-            fov: Deg(60.0).into(),
+            fov: 60.0_f32.to_radians(),
             depth: 0.1..50.0,
             blur_constant: 0.0,
             blur_linear: 0.0,
@@ -65,29 +68,38 @@ impl Default for Light {
     }
 }
 
+/// Light data in the storage buffer
 #[repr(C)]
 #[derive(Default, Debug, Clone, Copy)]
-pub struct Uniform {
-    /// ProjView matrix
-    pub proj_view: [[f32; 4]; 4],
+pub struct LightUniform {
     /// rgb, a:unused
     pub color: [f32; 4],
-    /// xyz, w: if w < 1.0 { dir } else { pos }
+    /// xyz, w:unused
     pub pos_dir: [f32; 4],
     /// xyz, w:unused
     pub stream: [f32; 4],
     /// x:constant, y:linear, z:quadratic, w:unused
     pub blur: [f32; 4],
-    /// x:cut_off, y:outer_cut_off, zw: unused
+    /// x:cos(cut_off), y:cos(outer_cut_off), zw: unused
     pub cut_off: [f32; 4],
-    /// x:shadow, yzw: unused
+    /// x:kind, y:shadow, zw: unused
     pub options: [u32; 4],
 }
 
-unsafe impl bytemuck::Pod for Uniform {}
-unsafe impl bytemuck::Zeroable for Uniform {}
+unsafe impl bytemuck::Pod for LightUniform {}
+unsafe impl bytemuck::Zeroable for LightUniform {}
+
+impl LightUniform {
+    /// Directional light kind
+    pub const AMBIENT: u32 = 0;
+    /// Point light kind
+    pub const POINT: u32 = 1;
+    /// Spot light kind
+    pub const SPOT: u32 = 2;
+}
 
 impl Light {
+    /// Constructs directional light
     pub fn ambient(dir_x: f32, dir_y: f32, dir_z: f32) -> Self {
         Self {
             position: Position::Ambient {
@@ -99,6 +111,7 @@ impl Light {
         }
     }
 
+    /// Constructs point light
     pub fn point(x: f32, y: f32, z: f32) -> Self {
         Self {
             position: Position::Point { x, y, z },
@@ -106,6 +119,15 @@ impl Light {
         }
     }
 
+    /// Constructs spot light
+    pub fn spot(x: f32, y: f32, z: f32) -> Self {
+        Self {
+            position: Position::Spot { x, y, z },
+            ..Default::default()
+        }
+    }
+
+    /// Sets color of the light, keeping intensity
     pub fn color(mut self, color: Color<f32>) -> Self {
         self.color.r = color.r;
         self.color.g = color.g;
@@ -113,23 +135,47 @@ impl Light {
         self
     }
 
+    /// Sets intensity of the light
     pub fn intensity(mut self, intensity: f32) -> Self {
         self.color.a = intensity;
         self
     }
 
+    /// Sets stream direction of the spot light
+    pub fn stream(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.stream = Vec3::new(x, y, z);
+        self
+    }
+
+    /// Sets attenuation of the point or spot light
+    pub fn attenuation(mut self, constant: f32, linear: f32, quadratic: f32) -> Self {
+        self.blur_constant = constant;
+        self.blur_linear = linear;
+        self.blur_quadratic = quadratic;
+        self
+    }
+
+    /// Sets inner and outer cut off angles of the spot light (rad)
+    pub fn cut_off(mut self, inner: f32, outer: f32) -> Self {
+        self.cut_off_inner = inner;
+        self.cut_off_outer = outer;
+        self
+    }
+
+    /// Enables or disables shadows casting
     pub fn shadow(mut self, shadow: bool) -> Self {
         self.shadow = shadow;
         self
     }
 
-    pub fn to_uniform(
-        &self,
-        scene: Vec3,
-        shadow_texture_width: u32,
-        shadow_texture_height: u32,
-        ambient_light_distance: f32,
-    ) -> Uniform {
+    /// Enables or disables the light
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Returns light data for the storage buffer
+    pub fn to_uniform(&self) -> LightUniform {
         const RESERVED_F32: f32 = 0.0;
         const RESERVED_U32: u32 = 0;
         let intensity = self.color.a;
@@ -139,21 +185,14 @@ impl Light {
             self.color.b * intensity,
             RESERVED_F32,
         ];
-        let (pos_dir, view_pos, depth_delta) = match self.position {
+        let (pos_dir, kind) = match self.position {
             Position::Ambient {
                 dir_x,
                 dir_y,
                 dir_z,
-            } => {
-                let dir = Vec3::new(dir_x, dir_y, dir_z);
-                let view_pos = -dir * ambient_light_distance + scene;
-                (
-                    [dir.x, dir.y, dir.z, 0.0],
-                    [view_pos.x, view_pos.y, view_pos.z],
-                    ambient_light_distance,
-                )
-            }
-            Position::Point { x, y, z } => ([x, y, z, 2.0], [x, y, z], 0.0),
+            } => ([dir_x, dir_y, dir_z, RESERVED_F32], LightUniform::AMBIENT),
+            Position::Point { x, y, z } => ([x, y, z, RESERVED_F32], LightUniform::POINT),
+            Position::Spot { x, y, z } => ([x, y, z, RESERVED_F32], LightUniform::SPOT),
         };
         let stream = [self.stream.x, self.stream.y, self.stream.z, RESERVED_F32];
         let blur = [
@@ -163,99 +202,20 @@ impl Light {
             RESERVED_F32,
         ];
         let cut_off = [
-            self.cut_off_inner,
-            self.cut_off_outer,
+            self.cut_off_inner.cos(),
+            self.cut_off_outer.cos(),
             RESERVED_F32,
             RESERVED_F32,
         ];
-        let options = [
-            if self.shadow { 1 } else { 0 },
-            RESERVED_U32,
-            RESERVED_U32,
-            RESERVED_U32,
-        ];
-        let depth = (self.depth.start + depth_delta)..(self.depth.end + depth_delta);
-        let proj = Camera::lens(self.fov, depth).proj(shadow_texture_width, shadow_texture_height);
-        let view = Camera::at(view_pos[0], view_pos[1], view_pos[2]).target(scene);
+        let options = [kind, u32::from(self.shadow), RESERVED_U32, RESERVED_U32];
 
-        Uniform {
+        LightUniform {
             color,
             pos_dir,
             stream,
             blur,
             cut_off,
             options,
-            proj_view: (proj * view).into(),
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Data {
-    pub number_of_lights: u32,
-    pub shadows: Vec<u32>,
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct LoadTask {
-    shadow_texture_width: u32,
-    shadow_texture_height: u32,
-    ambient_light_distance: f32,
-}
-
-impl LoadTask {
-    pub fn new(
-        shadow_texture_width: u32,
-        shadow_texture_height: u32,
-        ambient_light_distance: f32,
-    ) -> Self {
-        Self {
-            shadow_texture_width,
-            shadow_texture_height,
-            ambient_light_distance,
-        }
-    }
-}
-
-impl dotrix::Task for LoadTask {
-    type Context = (
-        dotrix::Any<crate::Buffers>,
-        dotrix::Ref<Gpu>,
-        dotrix::Ref<World>,
-    );
-
-    type Output = Data;
-
-    fn run(&mut self, (buffers, gpu, world): Self::Context) -> Self::Output {
-        // TODO: get current scene center from somewhere (need to find the best place)
-        let scene = Vec3::new(0.0, 0.0, 0.0);
-        let mut number_of_lights = 0;
-        let light_buffer = gpu.extract(&buffers.light);
-        let mut shadows = Vec::with_capacity(4);
-
-        for (light,) in world.query::<(&Light,)>() {
-            if light.enabled {
-                let uniform = light.to_uniform(
-                    scene,
-                    self.shadow_texture_width,
-                    self.shadow_texture_height,
-                    self.ambient_light_distance,
-                );
-                gpu.write_buffer(
-                    light_buffer,
-                    (std::mem::size_of::<Uniform>() as u32 * number_of_lights) as u64,
-                    bytemuck::cast_slice(&[uniform]),
-                );
-                if light.shadow {
-                    shadows.push(number_of_lights);
-                }
-                number_of_lights += 1;
-            }
-        }
-
-        Data {
-            number_of_lights,
-            shadows,
         }
     }
 }
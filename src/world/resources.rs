@@ -0,0 +1,134 @@
+//! Singleton resources of the world
+use std::any::{Any, TypeId};
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use super::{LockGuard, World};
+use crate::utils::Lock;
+
+/// Type-erased resource
+pub(super) type Resource = UnsafeCell<Box<dyn Any + Send + Sync>>;
+
+/// Marker type, resources are locked by, so they never share locks with components
+struct ResourceLock<R>(PhantomData<R>);
+
+/// Shared borrow of the world resource
+pub struct Res<'w, R> {
+    resource: &'w R,
+    _guard: LockGuard,
+}
+
+/// Exclusive borrow of the world resource
+pub struct ResMut<'w, R> {
+    resource: &'w mut R,
+    _guard: LockGuard,
+}
+
+impl<'w, R> Deref for Res<'w, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        self.resource
+    }
+}
+
+impl<'w, R> Deref for ResMut<'w, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        self.resource
+    }
+}
+
+impl<'w, R> DerefMut for ResMut<'w, R> {
+    fn deref_mut(&mut self) -> &mut R {
+        self.resource
+    }
+}
+
+impl World {
+    /// Inserts the resource, replacing and returning the existing one of the same type
+    pub fn insert_resource<R: Any + Send + Sync>(&mut self, resource: R) -> Option<R> {
+        self.resources
+            .insert(TypeId::of::<R>(), UnsafeCell::new(Box::new(resource)))
+            .and_then(|previous| previous.into_inner().downcast::<R>().ok())
+            .map(|previous| *previous)
+    }
+
+    /// Removes the resource and returns it
+    pub fn remove_resource<R: Any + Send + Sync>(&mut self) -> Option<R> {
+        self.resources
+            .remove(&TypeId::of::<R>())
+            .and_then(|resource| resource.into_inner().downcast::<R>().ok())
+            .map(|resource| *resource)
+    }
+
+    /// Returns true if the world holds the resource
+    pub fn contains_resource<R: Any + Send + Sync>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    /// Borrows the resource for reading
+    ///
+    /// Waits until the resource is not borrowed mutably, the same way as queries wait for
+    /// components.
+    pub fn resource<R: Any + Send + Sync>(&self) -> Option<Res<'_, R>> {
+        let resource = self.resources.get(&TypeId::of::<R>())?;
+        let guard = self.acquire(vec![Lock::ReadOnly(TypeId::of::<ResourceLock<R>>())]);
+        // NOTE: the lock guarantees there are no mutable borrows
+        let resource = unsafe { &*resource.get() };
+        Some(Res {
+            resource: resource.downcast_ref::<R>()?,
+            _guard: guard,
+        })
+    }
+
+    /// Borrows the resource for reading and writing
+    ///
+    /// Waits until the resource is not borrowed, the same way as queries wait for components.
+    pub fn resource_mut<R: Any + Send + Sync>(&self) -> Option<ResMut<'_, R>> {
+        let resource = self.resources.get(&TypeId::of::<R>())?;
+        let guard = self.acquire(vec![Lock::ReadWrite(TypeId::of::<ResourceLock<R>>())]);
+        // NOTE: the lock guarantees there are no other borrows
+        let resource = unsafe { &mut *resource.get() };
+        Some(ResMut {
+            resource: resource.downcast_mut::<R>()?,
+            _guard: guard,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::World;
+
+    struct Gravity(f32);
+    struct Velocity(f32);
+
+    #[test]
+    fn resources_are_borrowed_alongside_components() {
+        let mut world = World::new();
+        world.spawn([(Velocity(0.0),), (Velocity(1.0),)]).count();
+        assert!(world.insert_resource(Gravity(-9.8)).is_none());
+        assert!(world.resource::<u32>().is_none());
+
+        {
+            let gravity = world.resource::<Gravity>().unwrap();
+            // shared borrows do not block each other
+            assert_eq!(world.resource::<Gravity>().unwrap().0, gravity.0);
+            for (velocity,) in world.query::<(&mut Velocity,)>() {
+                velocity.0 += gravity.0;
+            }
+        }
+        world.resource_mut::<Gravity>().unwrap().0 = -1.0;
+
+        let velocities = world
+            .query::<(&Velocity,)>()
+            .map(|(velocity,)| velocity.0)
+            .collect::<Vec<_>>();
+        assert_eq!(velocities, vec![-9.8, -8.8]);
+        assert_eq!(world.remove_resource::<Gravity>().map(|g| g.0), Some(-1.0));
+        assert!(!world.contains_resource::<Gravity>());
+    }
+}
@@ -0,0 +1,427 @@
+//! World snapshots for scene files and save-games
+//!
+//! Only components and resources of types registered in the [`Registry`] are saved. Snapshot can be stored as
+//! human-readable JSON, or as compact MessagePack binary. References to assets are saved by
+//! asset names, so snapshots stay valid between runs.
+use std::any::{Any, TypeId};
+use std::cell::UnsafeCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::resources::Resource;
+use super::{Entity, World};
+use crate::loaders::{Asset, Assets};
+use crate::log;
+use crate::models::{Armature, Children, Material, Mesh, Parent, Transform};
+use crate::utils::Id;
+
+/// Format of the snapshot file
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SnapshotFormat {
+    /// Human-readable JSON
+    Json,
+    /// Compact MessagePack binary
+    Binary,
+}
+
+/// Serialized entities of the world
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Serialized entities
+    pub entities: Vec<EntitySnapshot>,
+    /// Resources by their registered names
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resources: BTreeMap<String, Value>,
+}
+
+/// Serialized entity
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    /// ID of the entity, a new one is generated on restore if it is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    /// Components by their registered names
+    pub components: BTreeMap<String, Value>,
+}
+
+impl Snapshot {
+    /// Loads snapshot from the file
+    pub fn load(path: impl AsRef<Path>, format: SnapshotFormat) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        match format {
+            SnapshotFormat::Json => Self::read_json(reader),
+            SnapshotFormat::Binary => Self::read_binary(reader),
+        }
+    }
+
+    /// Saves snapshot into the file
+    pub fn save(&self, path: impl AsRef<Path>, format: SnapshotFormat) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format {
+            SnapshotFormat::Json => self.write_json(&mut writer)?,
+            SnapshotFormat::Binary => self.write_binary(&mut writer)?,
+        }
+        writer.flush()
+    }
+
+    /// Reads snapshot in JSON format
+    pub fn read_json(reader: impl Read) -> io::Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Writes snapshot in JSON format
+    pub fn write_json(&self, writer: impl Write) -> io::Result<()> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// Reads snapshot in binary format
+    pub fn read_binary(reader: impl Read) -> io::Result<Self> {
+        rmp_serde::from_read(reader).map_err(|error| invalid_data(error.to_string()))
+    }
+
+    /// Writes snapshot in binary format
+    pub fn write_binary(&self, mut writer: impl Write) -> io::Result<()> {
+        // fields are named, so optional ones can be skipped
+        rmp_serde::encode::write_named(&mut writer, self).map_err(io::Error::other)
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+type SaveFn =
+    dyn Fn(&World, &Assets, &mut dyn FnMut(Id<Entity>, Value)) -> io::Result<()> + Send + Sync;
+type LoadFn = dyn Fn(Value, &Assets, &mut Entity) -> io::Result<()> + Send + Sync;
+type SaveResourceFn = dyn Fn(&World) -> io::Result<Option<Value>> + Send + Sync;
+type LoadResourceFn = dyn Fn(Value) -> io::Result<Resource> + Send + Sync;
+
+/// Serializable component type
+struct Registration {
+    name: String,
+    save: Box<SaveFn>,
+    load: Box<LoadFn>,
+}
+
+/// Serializable resource type
+struct ResourceRegistration {
+    type_id: TypeId,
+    save: Box<SaveResourceFn>,
+    load: Box<LoadResourceFn>,
+}
+
+/// Registry of component and resource types, that can be saved into a [`Snapshot`]
+#[derive(Default)]
+pub struct Registry {
+    components: Vec<Registration>,
+    index: HashMap<String, usize>,
+    resources: BTreeMap<String, ResourceRegistration>,
+}
+
+impl Registry {
+    /// Constructs an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs registry of engine components: `transform`, `parent`, `children`, `mesh`,
+    /// `material` and `armature`
+    pub fn engine() -> Self {
+        Self::new()
+            .register::<Transform>("transform")
+            .register::<Parent>("parent")
+            .register::<Children>("children")
+            .register_asset::<Mesh>("mesh")
+            .register_asset::<Material>("material")
+            .register_asset::<Armature>("armature")
+    }
+
+    /// Registers the component type under the `name`
+    pub fn register<C>(self, name: impl Into<String>) -> Self
+    where
+        C: Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.add(Registration {
+            name: name.into(),
+            save: Box::new(|world, _, store| {
+                for (id, component) in world.query::<(&Id<Entity>, &C)>() {
+                    store(*id, serde_json::to_value(component)?);
+                }
+                Ok(())
+            }),
+            load: Box::new(|value, _, entity| {
+                entity.insert(serde_json::from_value::<C>(value)?);
+                Ok(())
+            }),
+        })
+    }
+
+    /// Registers the asset reference component `Id<A>` under the `name`
+    ///
+    /// Reference is saved as the asset name and resolved through [`Assets::find`] on restore.
+    /// Null references are saved as `null`.
+    pub fn register_asset<A: Asset>(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        let asset_type = name.clone();
+        self.add(Registration {
+            name,
+            save: Box::new(|world, assets, store| {
+                for (id, asset_id) in world.query::<(&Id<Entity>, &Id<A>)>() {
+                    let value = match assets.get(*asset_id) {
+                        Some(asset) => Value::String(String::from(asset.name())),
+                        None => {
+                            if !asset_id.is_null() {
+                                log::warn!("Asset {:?} of entity {:?} is not loaded", asset_id, id);
+                            }
+                            Value::Null
+                        }
+                    };
+                    store(*id, value);
+                }
+                Ok(())
+            }),
+            load: Box::new(move |value, assets, entity| {
+                let asset_id = match serde_json::from_value::<Option<String>>(value)? {
+                    Some(asset_name) => assets.find::<A>(&asset_name).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("Asset `{}` of `{}` is not loaded", asset_name, asset_type),
+                        )
+                    })?,
+                    None => Id::null(),
+                };
+                entity.insert(asset_id);
+                Ok(())
+            }),
+        })
+    }
+
+    /// Registers the resource type under the `name`
+    pub fn register_resource<R>(mut self, name: impl Into<String>) -> Self
+    where
+        R: Serialize + DeserializeOwned + Any + Send + Sync,
+    {
+        let name = name.into();
+        let registration = ResourceRegistration {
+            type_id: TypeId::of::<R>(),
+            save: Box::new(|world| {
+                world
+                    .resource::<R>()
+                    .map(|resource| serde_json::to_value(&*resource))
+                    .transpose()
+                    .map_err(io::Error::from)
+            }),
+            load: Box::new(|value| {
+                let resource = serde_json::from_value::<R>(value)?;
+                Ok(UnsafeCell::new(Box::new(resource)))
+            }),
+        };
+        if self.resources.insert(name.clone(), registration).is_some() {
+            log::warn!("Resource `{}` is registered twice", name);
+        }
+        self
+    }
+
+    fn add(mut self, registration: Registration) -> Self {
+        match self.index.get(&registration.name) {
+            Some(&index) => {
+                log::warn!("Component `{}` is registered twice", registration.name);
+                self.components[index] = registration;
+            }
+            None => {
+                self.index
+                    .insert(registration.name.clone(), self.components.len());
+                self.components.push(registration);
+            }
+        }
+        self
+    }
+}
+
+impl World {
+    /// Returns snapshot of entities having components registered in the `registry`, and of
+    /// registered resources
+    pub fn snapshot(&self, registry: &Registry, assets: &Assets) -> io::Result<Snapshot> {
+        let mut entities: Vec<EntitySnapshot> = Vec::new();
+        let mut index: HashMap<Id<Entity>, usize> = HashMap::new();
+
+        for registration in registry.components.iter() {
+            (registration.save)(self, assets, &mut |id, value| {
+                let entity_index = *index.entry(id).or_insert_with(|| {
+                    entities.push(EntitySnapshot {
+                        id: Some(*id.uuid()),
+                        components: BTreeMap::new(),
+                    });
+                    entities.len() - 1
+                });
+                entities[entity_index]
+                    .components
+                    .insert(registration.name.clone(), value);
+            })?;
+        }
+
+        let mut resources = BTreeMap::new();
+        for (name, registration) in registry.resources.iter() {
+            if let Some(value) = (registration.save)(self)? {
+                resources.insert(name.clone(), value);
+            }
+        }
+
+        Ok(Snapshot {
+            entities,
+            resources,
+        })
+    }
+
+    /// Spawns entities and inserts resources of the snapshot, returns IDs of the entities
+    ///
+    /// Existing entities with the same IDs and existing resources are replaced. If any of
+    /// components or resources can not be restored, an error is returned and the world stays
+    /// unchanged.
+    pub fn restore(
+        &mut self,
+        snapshot: Snapshot,
+        registry: &Registry,
+        assets: &Assets,
+    ) -> io::Result<Vec<Id<Entity>>> {
+        let entities = snapshot
+            .entities
+            .into_iter()
+            .map(|entity_snapshot| {
+                let id = entity_snapshot.id.map(Id::from).unwrap_or_else(Id::new);
+                let mut entity = Entity::empty();
+                for (name, value) in entity_snapshot.components {
+                    let registration = registry
+                        .index
+                        .get(&name)
+                        .map(|&index| &registry.components[index])
+                        .ok_or_else(|| invalid_data(format!("Unknown component `{}`", name)))?;
+                    (registration.load)(value, assets, &mut entity).map_err(|error| {
+                        io::Error::new(
+                            error.kind(),
+                            format!("Could not restore `{}` of {:?}: {}", name, id, error),
+                        )
+                    })?;
+                }
+                Ok((id, entity))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let resources = snapshot
+            .resources
+            .into_iter()
+            .map(|(name, value)| {
+                let registration = registry
+                    .resources
+                    .get(&name)
+                    .ok_or_else(|| invalid_data(format!("Unknown resource `{}`", name)))?;
+                let resource = (registration.load)(value).map_err(|error| {
+                    io::Error::new(
+                        error.kind(),
+                        format!("Could not restore resource `{}`: {}", name, error),
+                    )
+                })?;
+                Ok((registration.type_id, resource))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        self.resources.extend(resources);
+        Ok(entities
+            .into_iter()
+            .map(|(id, entity)| {
+                self.exile(&id);
+                self.spawn_with_id(id, entity);
+                id
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Registry, Snapshot};
+    use crate::loaders::Assets;
+    use crate::math::Vec3;
+    use crate::models::{attach, Children, Material, Mesh, Parent, Transform};
+    use crate::utils::Id;
+    use crate::world::World;
+
+    #[test]
+    fn world_is_saved_and_restored() {
+        let mut assets = Assets::new();
+        let mesh = assets.set(Mesh::new(String::from("tank::mesh")));
+        let registry = Registry::engine().register_resource::<u64>("seed");
+
+        let mut world = World::new();
+        let mut ids = world
+            .spawn([
+                (
+                    Transform::from_translation(Vec3::new(1.0, 2.0, 3.0)),
+                    mesh,
+                    Id::<Material>::null(),
+                ),
+                (
+                    Transform::from_scale_factor(2.0),
+                    Id::<Mesh>::null(),
+                    Id::<Material>::null(),
+                ),
+            ])
+            .collect::<Vec<_>>();
+        // not registered components are skipped
+        world.spawn([(0.5_f32,)]).count();
+        world.insert_resource(42_u64);
+        assert!(attach(&mut world, ids[1], Parent::new(ids[0])));
+
+        let snapshot = world.snapshot(&registry, &assets).unwrap();
+        assert_eq!(snapshot.entities.len(), 2);
+        assert_eq!(snapshot.resources.len(), 1);
+
+        let mut json = Vec::new();
+        snapshot.write_json(&mut json).unwrap();
+        assert_eq!(Snapshot::read_json(json.as_slice()).unwrap(), snapshot);
+        let mut binary = Vec::new();
+        snapshot.write_binary(&mut binary).unwrap();
+        assert!(binary.len() < json.len());
+        let snapshot = Snapshot::read_binary(binary.as_slice()).unwrap();
+
+        // unresolved assets are reported
+        assert!(World::new()
+            .restore(snapshot.clone(), &registry, &Assets::new())
+            .is_err());
+
+        let mut restored = World::new();
+        let mut restored_ids = restored.restore(snapshot, &registry, &assets).unwrap();
+        ids.sort_by_key(|id| *id.uuid());
+        restored_ids.sort_by_key(|id| *id.uuid());
+        assert_eq!(restored_ids, ids);
+        assert_eq!(restored.resource::<u64>().map(|seed| *seed), Some(42));
+
+        let transform = restored.component_mut::<Transform>(&ids[0]).copied();
+        let expected = world.component_mut::<Transform>(&ids[0]).copied();
+        assert_eq!(
+            transform.map(|transform| transform.matrix()),
+            expected.map(|transform| transform.matrix())
+        );
+        for id in ids.iter() {
+            assert_eq!(
+                restored.component_mut::<Id<Mesh>>(id).copied(),
+                world.component_mut::<Id<Mesh>>(id).copied()
+            );
+            assert_eq!(
+                restored.component_mut::<Parent>(id).copied(),
+                world.component_mut::<Parent>(id).copied()
+            );
+            assert_eq!(
+                restored.component_mut::<Children>(id).cloned(),
+                world.component_mut::<Children>(id).cloned()
+            );
+        }
+    }
+}
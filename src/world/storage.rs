@@ -1,14 +1,70 @@
+use std::alloc::{self, Layout};
 use std::any::{Any, TypeId};
-use std::cell::UnsafeCell;
-use std::collections::{hash_map, HashMap};
-use std::marker::PhantomData;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::utils::Id;
 
 /// Entity structure has only id field and represent an agregation of components
 pub struct Entity {
-    map: HashMap<TypeId, Box<dyn Any>>,
+    map: HashMap<TypeId, (ComponentInfo, Box<dyn Any>)>,
+    /// Change ticks of components taken from a container
+    ticks: HashMap<TypeId, ComponentTicks>,
+}
+
+/// Ticks of the component addition and last mutable borrow
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct ComponentTicks {
+    /// Tick of the component addition
+    pub added: u64,
+    /// Tick of the last mutable borrow of the component
+    pub changed: u64,
+}
+
+impl ComponentTicks {
+    fn new(tick: u64) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+}
+
+/// Type erased information about the component type
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentInfo {
+    type_id: TypeId,
+    layout: Layout,
+    drop: unsafe fn(*mut u8),
+    into_box: unsafe fn(*mut u8) -> Box<dyn Any>,
 }
 
-pub type ComponentsList = Vec<Option<UnsafeCell<Box<dyn Any>>>>;
+impl ComponentInfo {
+    /// Returns information about the component type `T`
+    pub fn of<T: Any>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            layout: Layout::new::<T>(),
+            drop: drop_component::<T>,
+            into_box: box_component::<T>,
+        }
+    }
+
+    /// Returns [`TypeId`] of the component
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+unsafe fn drop_component<T>(ptr: *mut u8) {
+    ptr.cast::<T>().drop_in_place();
+}
+
+unsafe fn box_component<T: Any>(ptr: *mut u8) -> Box<dyn Any> {
+    Box::new(ptr.cast::<T>().read())
+}
 
 impl Entity {
     pub fn new<T: IntoEntity>(tuple: T) -> Self {
@@ -18,53 +74,39 @@ impl Entity {
     pub fn empty() -> Self {
         Self {
             map: HashMap::new(),
+            ticks: HashMap::new(),
         }
     }
 
     pub fn with<T: Any>(mut self, component: T) -> Self {
-        self.map.insert(TypeId::of::<T>(), Box::new(component));
+        self.insert(component);
         self
     }
 
-    pub fn set_raw(&mut self, component_type_id: TypeId, component: Box<dyn Any>) {
-        self.map.insert(component_type_id, component);
+    /// Inserts the component into the entity, replacing the existing one of the same type
+    pub fn insert<T: Any>(&mut self, component: T) {
+        self.map.insert(
+            TypeId::of::<T>(),
+            (ComponentInfo::of::<T>(), Box::new(component)),
+        );
     }
 
-    pub fn archetype(&self) -> Archetype {
-        Archetype {
-            inner: self.map.keys(),
-            len: self.map.len(),
-        }
-    }
-}
-
-pub struct Archetype<'a> {
-    inner: hash_map::Keys<'a, TypeId, Box<dyn Any>>,
-    len: usize,
-}
-
-impl<'a> Archetype<'a> {
-    pub fn len(&self) -> usize {
-        self.len
+    /// Forgets change ticks of components, so they are considered new when stored again
+    pub fn reset_ticks(&mut self) {
+        self.ticks.clear();
     }
-}
-
-impl<'a> Iterator for Archetype<'a> {
-    type Item = &'a TypeId;
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    /// Takes the component out of the entity
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|(_, component)| component.downcast::<T>().ok())
+            .map(|component| *component)
     }
-}
 
-impl IntoIterator for Entity {
-    type Item = (TypeId, Box<dyn Any>);
-    type IntoIter = hash_map::IntoIter<TypeId, Box<dyn Any>>;
-
-    #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
+    /// Returns information about components of the entity
+    pub fn archetype(&self) -> Vec<ComponentInfo> {
+        self.map.values().map(|(info, _)| *info).collect()
     }
 }
 
@@ -72,6 +114,15 @@ pub trait IntoEntity {
     fn entity(self) -> Entity;
     /// Returns true if object of this type does not have fixed set of components, false otherwise
     fn volatile() -> bool;
+    /// Returns information about components of the fixed set, including the `Id<Entity>`
+    ///
+    /// `None` means that the object can not be stored directly into columns and must be
+    /// converted into [`Entity`] first.
+    fn components() -> Option<Vec<ComponentInfo>> {
+        None
+    }
+    /// Stores the object with the `id` into the container, matching its archetype
+    fn store(self, container: &mut Container, id: Id<Entity>, tick: u64) -> usize;
 }
 
 impl IntoEntity for Entity {
@@ -82,6 +133,10 @@ impl IntoEntity for Entity {
     fn volatile() -> bool {
         true
     }
+
+    fn store(self, container: &mut Container, id: Id<Entity>, tick: u64) -> usize {
+        container.store(self.with(id), tick)
+    }
 }
 
 macro_rules! impl_into_components_map {
@@ -93,18 +148,34 @@ macro_rules! impl_into_components_map {
             #[allow(non_snake_case)]
             fn entity(self) -> Entity {
                 let ($($i,)*) = self;
-                let map = [
-                    $((TypeId::of::<$i>(), Box::new($i) as Box<dyn Any>),)*
-                ]
-                .into_iter()
-                .collect::<HashMap<_,_>>();
-                Entity {
-                    map
-                }
+                Entity::empty()$(.with($i))*
             }
+
             fn volatile() -> bool {
                 false
             }
+
+            fn components() -> Option<Vec<ComponentInfo>> {
+                let archetype = vec![
+                    ComponentInfo::of::<Id<Entity>>(),
+                    $(ComponentInfo::of::<$i>(),)*
+                ];
+                // components of the same type would be merged in an entity
+                let unique = archetype
+                    .iter()
+                    .enumerate()
+                    .all(|(i, info)| archetype[..i].iter().all(|other| other.type_id != info.type_id));
+                unique.then_some(archetype)
+            }
+
+            #[allow(non_snake_case)]
+            fn store(self, container: &mut Container, id: Id<Entity>, tick: u64) -> usize {
+                let ($($i,)*) = self;
+                let ticks = ComponentTicks::new(tick);
+                container.push(id, ticks);
+                $(container.push($i, ticks);)*
+                container.commit()
+            }
         }
     }
 }
@@ -126,171 +197,441 @@ impl_into_components_map!((A, B, C, D, E, F, G, H, I, J, K, L, M, N));
 impl_into_components_map!((A, B, C, D, E, F, G, H, I, J, K, L, M, N, O));
 impl_into_components_map!((A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P));
 
+/// Change ticks of the component stored in a column
+struct TicksCell {
+    added: u64,
+    changed: AtomicU64,
+}
+
+impl TicksCell {
+    fn new(ticks: ComponentTicks) -> Self {
+        Self {
+            added: ticks.added,
+            changed: AtomicU64::new(ticks.changed),
+        }
+    }
+
+    fn get(&self) -> ComponentTicks {
+        ComponentTicks {
+            added: self.added,
+            changed: self.changed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Borrowed component is exclusively owned by the caller, so a plain store is enough
+    #[inline]
+    fn mark_changed(&self, tick: u64) {
+        if self.changed.load(Ordering::Relaxed) < tick {
+            self.changed.store(tick, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Type erased contiguous vector of components of the same type
+struct Column {
+    info: ComponentInfo,
+    data: NonNull<u8>,
+    capacity: usize,
+    len: usize,
+    ticks: Vec<TicksCell>,
+}
+
+impl Column {
+    fn new(info: ComponentInfo) -> Self {
+        let size = info.layout.size();
+        Self {
+            info,
+            // NOTE: alignment is a non-zero power of two, so it is a valid dangling pointer
+            data: NonNull::new(info.layout.align() as *mut u8).unwrap(),
+            capacity: if size == 0 { usize::MAX } else { 0 },
+            len: 0,
+            ticks: Vec::new(),
+        }
+    }
+
+    fn array_layout(&self, capacity: usize) -> Layout {
+        let size = self
+            .info
+            .layout
+            .size()
+            .checked_mul(capacity)
+            .expect("Column is too large");
+        Layout::from_size_align(size, self.info.layout.align()).expect("Column is too large")
+    }
+
+    fn grow(&mut self) {
+        let capacity = (self.capacity * 2).max(4);
+        let layout = self.array_layout(capacity);
+        let data = unsafe {
+            if self.capacity == 0 {
+                alloc::alloc(layout)
+            } else {
+                alloc::realloc(
+                    self.data.as_ptr(),
+                    self.array_layout(self.capacity),
+                    layout.size(),
+                )
+            }
+        };
+        self.data = NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        self.capacity = capacity;
+    }
+
+    #[inline]
+    fn ptr(&self, index: usize) -> *mut u8 {
+        unsafe { self.data.as_ptr().add(index * self.info.layout.size()) }
+    }
+
+    /// Moves the component from `src` into the end of the column
+    ///
+    /// Safety: `src` must point to a value of the column type, that is not used afterwards
+    unsafe fn push_raw(&mut self, src: *const u8, ticks: ComponentTicks) {
+        if self.len == self.capacity {
+            self.grow();
+        }
+        std::ptr::copy_nonoverlapping(src, self.ptr(self.len), self.info.layout.size());
+        self.len += 1;
+        self.ticks.push(TicksCell::new(ticks));
+    }
+
+    fn push<T: Any>(&mut self, component: T, ticks: ComponentTicks) {
+        debug_assert_eq!(self.info.type_id, TypeId::of::<T>());
+        let component = std::mem::ManuallyDrop::new(component);
+        unsafe { self.push_raw(&*component as *const T as *const u8, ticks) };
+    }
+
+    fn push_boxed(&mut self, component: Box<dyn Any>, ticks: ComponentTicks) {
+        debug_assert_eq!(self.info.type_id, (*component).type_id());
+        let ptr = Box::into_raw(component) as *mut u8;
+        unsafe {
+            self.push_raw(ptr, ticks);
+            // value was moved out, so only the memory of the box is released
+            if self.info.layout.size() != 0 {
+                alloc::dealloc(ptr, self.info.layout);
+            }
+        }
+    }
+
+    /// Removes the component, moving the last one into its place
+    fn swap_remove(&mut self, index: usize) -> (Box<dyn Any>, ComponentTicks) {
+        assert!(index < self.len, "Column index is out of bounds");
+        let last = self.len - 1;
+        let component = unsafe { (self.info.into_box)(self.ptr(index)) };
+        if index != last {
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    self.ptr(last),
+                    self.ptr(index),
+                    self.info.layout.size(),
+                )
+            };
+        }
+        self.len = last;
+        let ticks = self.ticks.swap_remove(index).get();
+        (component, ticks)
+    }
+
+    /// Returns components in the range, clamped to the column length
+    fn slice<T: Any>(&self, range: Range<usize>) -> &[T] {
+        debug_assert_eq!(self.info.type_id, TypeId::of::<T>());
+        let range = self.clamp(range);
+        unsafe { std::slice::from_raw_parts(self.ptr(range.start) as *const T, range.len()) }
+    }
+
+    /// Returns mutable components in the range, clamped to the column length
+    ///
+    /// Safety: caller must guarantee that components are not borrowed elsewhere
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn slice_mut<T: Any>(&self, range: Range<usize>) -> &mut [T] {
+        debug_assert_eq!(self.info.type_id, TypeId::of::<T>());
+        let range = self.clamp(range);
+        std::slice::from_raw_parts_mut(self.ptr(range.start) as *mut T, range.len())
+    }
+
+    fn clamp(&self, range: Range<usize>) -> Range<usize> {
+        range.start.min(self.len)..range.end.min(self.len)
+    }
+}
+
+impl Drop for Column {
+    fn drop(&mut self) {
+        for index in 0..self.len {
+            unsafe { (self.info.drop)(self.ptr(index)) };
+        }
+        if self.info.layout.size() != 0 && self.capacity != 0 {
+            unsafe { alloc::dealloc(self.data.as_ptr(), self.array_layout(self.capacity)) };
+        }
+    }
+}
+
 /// Stores Entities of the same archetype
 pub struct Container {
     /// TypeId identifies component
-    /// Vec stores components of different entities
-    data: HashMap<TypeId, ComponentsList>,
-    removed: Vec<usize>,
+    /// Column stores components of different entities
+    columns: HashMap<TypeId, Column>,
     len: usize,
 }
 
 impl Container {
+    /// Constructs an empty container of the archetype
+    pub fn new(archetype: &[ComponentInfo]) -> Self {
+        Self {
+            columns: archetype
+                .iter()
+                .map(|info| (info.type_id, Column::new(*info)))
+                .collect::<HashMap<_, _>>(),
+            len: 0,
+        }
+    }
+
     pub fn contains(&self, component_type_id: TypeId) -> bool {
-        self.data.contains_key(&component_type_id)
+        self.columns.contains_key(&component_type_id)
     }
 
-    pub fn matches(&self, archetype: &mut Archetype) -> bool {
-        !archetype.any(|component_type_id| !self.data.contains_key(component_type_id))
+    /// Checks if the container stores exactly the archetype
+    pub fn matches(&self, archetype: &[ComponentInfo]) -> bool {
+        archetype.len() == self.columns.len()
+            && archetype
+                .iter()
+                .all(|info| self.columns.contains_key(&info.type_id))
     }
 
-    pub fn store(&mut self, entity: Entity) -> usize {
-        let index = self.removed.pop().unwrap_or_else(|| self.next_index());
+    /// Stores the entity, its new components are marked as added at the `tick`
+    pub fn store(&mut self, entity: Entity, tick: u64) -> usize {
+        let Entity { map, ticks } = entity;
 
-        for (component_type_id, component) in entity.into_iter() {
-            self.data
+        for (component_type_id, (_, component)) in map.into_iter() {
+            let ticks = ticks
+                .get(&component_type_id)
+                .copied()
+                .unwrap_or(ComponentTicks::new(tick));
+            self.columns
                 .get_mut(&component_type_id)
                 .expect("Entity should match container")
-                .insert(index, Some(UnsafeCell::new(component)));
+                .push_boxed(component, ticks);
         }
 
+        self.commit()
+    }
+
+    /// Pushes the component of a new entity, the entity is stored by [`Container::commit`]
+    fn push<C: Any>(&mut self, component: C, ticks: ComponentTicks) {
+        self.columns
+            .get_mut(&TypeId::of::<C>())
+            .expect("Entity should match container")
+            .push(component, ticks);
+    }
+
+    /// Completes storing of an entity, which components were pushed into columns
+    fn commit(&mut self) -> usize {
+        let index = self.len;
+        self.len += 1;
+        debug_assert!(self.columns.values().all(|column| column.len == self.len));
         index
     }
 
-    pub fn remove(&mut self, index: usize) -> Entity {
+    /// Removes the entity, moving the last entity of the container into its place
+    ///
+    /// Returns the removed entity and the ID of the moved one, if any
+    pub fn remove(&mut self, index: usize) -> (Entity, Option<Id<Entity>>) {
         let mut entity = Entity::empty();
-        for (type_id, list) in self.data.iter_mut() {
-            if let Some(component) = list[index].take() {
-                entity.set_raw(*type_id, component.into_inner());
-            }
+        for (type_id, column) in self.columns.iter_mut() {
+            let (component, ticks) = column.swap_remove(index);
+            entity.ticks.insert(*type_id, ticks);
+            entity.map.insert(*type_id, (column.info, component));
         }
-        self.removed.push(index);
-        entity
+        self.len -= 1;
+        let moved = if index < self.len {
+            self.get::<Id<Entity>>(index).copied()
+        } else {
+            None
+        };
+        (entity, moved)
     }
 
     pub fn get<C: Any>(&self, entity_index: usize) -> Option<&C> {
-        self.data
+        self.columns
             .get(&TypeId::of::<C>())
-            .and_then(|list| list[entity_index].as_ref())
-            .map(|value| unsafe { (*(value.get())).downcast_ref::<C>().unwrap() })
+            .and_then(|column| column.slice::<C>(entity_index..entity_index + 1).first())
     }
 
-    pub unsafe fn get_mut<C: Any>(&self, entity_index: usize) -> Option<&mut C> {
-        self.data
+    /// Returns mutable reference to the component, marking it as changed at the `tick`
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_mut<C: Any>(&self, entity_index: usize, tick: u64) -> Option<&mut C> {
+        let column = self.columns.get(&TypeId::of::<C>())?;
+        let component = column
+            .slice_mut::<C>(entity_index..entity_index + 1)
+            .first_mut()?;
+        column.ticks[entity_index].mark_changed(tick);
+        Some(component)
+    }
+
+    /// Returns change ticks of the component
+    pub fn ticks<C: Any>(&self, entity_index: usize) -> Option<ComponentTicks> {
+        self.columns
             .get(&TypeId::of::<C>())
-            .and_then(|list| list[entity_index].as_ref())
-            .map(|value| (*(value.get())).downcast_mut::<C>().unwrap())
+            .and_then(|column| column.ticks.get(entity_index))
+            .map(|ticks| ticks.get())
     }
 
-    pub fn iter<C: Any>(&self) -> Iter<C> {
-        Iter {
-            inner: self.data.get(&TypeId::of::<C>()).unwrap().iter(),
-            _phantom_data: PhantomData,
-        }
+    /// Returns mutable borrow of the component, that is marked as changed at the `tick` only
+    /// when taken
+    pub unsafe fn borrow_mut<C: Any>(
+        &self,
+        entity_index: usize,
+        tick: u64,
+    ) -> Option<ComponentMut<'_, C>> {
+        let column = self.columns.get(&TypeId::of::<C>())?;
+        let component = column
+            .slice_mut::<C>(entity_index..entity_index + 1)
+            .first_mut()?;
+        Some(ComponentMut {
+            component,
+            ticks: &column.ticks[entity_index],
+            tick,
+        })
+    }
+
+    pub fn iter<C: Any>(&self) -> Iter<'_, C> {
+        self.iter_range(0..self.len)
     }
 
-    pub unsafe fn iter_mut<C: Any>(&self) -> IterMut<C> {
+    /// Returns iterator over mutable borrows of components, see [`ComponentMut`]
+    pub unsafe fn iter_mut<C: Any>(&self, tick: u64) -> IterMut<'_, C> {
+        self.iter_mut_range(0..self.len, tick)
+    }
+
+    pub fn iter_range<C: Any>(&self, range: Range<usize>) -> Iter<'_, C> {
+        self.column::<C>().slice(range).iter()
+    }
+
+    /// Returns iterator over mutable borrows of components in the range, see [`ComponentMut`]
+    pub unsafe fn iter_mut_range<C: Any>(&self, range: Range<usize>, tick: u64) -> IterMut<'_, C> {
+        let column = self.column::<C>();
+        let range = column.clamp(range);
         IterMut {
-            inner: self.data.get(&TypeId::of::<C>()).unwrap().iter(),
-            _phantom_data: PhantomData,
+            inner: column.slice_mut(range.clone()).iter_mut(),
+            ticks: column.ticks[range].iter(),
+            tick,
         }
     }
 
-    pub fn has(&self, component_type_id: TypeId) -> bool {
-        self.data.contains_key(&component_type_id)
+    /// Returns iterator over change ticks of components in the range
+    pub fn ticks_range<C: Any>(&self, range: Range<usize>) -> TicksIter<'_> {
+        let column = self.column::<C>();
+        TicksIter {
+            inner: column.ticks[column.clamp(range)].iter(),
+        }
     }
 
-    pub fn count_components(&self) -> usize {
-        self.data.len()
+    /// Returns number of entity slots
+    pub fn slots(&self) -> usize {
+        self.len
     }
 
-    fn next_index(&mut self) -> usize {
-        let index = self.len;
-        self.len += 1;
-        index
+    fn column<C: Any>(&self) -> &Column {
+        self.columns.get(&TypeId::of::<C>()).unwrap()
+    }
+
+    pub fn has(&self, component_type_id: TypeId) -> bool {
+        self.columns.contains_key(&component_type_id)
     }
-}
 
-pub struct Iter<'a, C> {
-    inner: std::slice::Iter<'a, Option<UnsafeCell<Box<dyn Any>>>>,
-    _phantom_data: PhantomData<C>,
+    pub fn count_components(&self) -> usize {
+        self.columns.len()
+    }
 }
 
-impl<'a, C: Any> Iterator for Iter<'a, C> {
-    type Item = &'a C;
+/// Iterator over components of a column
+pub type Iter<'a, C> = std::slice::Iter<'a, C>;
+
+/// Mutable borrow of the component
+///
+/// The component is marked as changed only when it is taken, so entities filtered out by a
+/// query keep their change ticks.
+pub struct ComponentMut<'a, C> {
+    component: &'a mut C,
+    ticks: &'a TicksCell,
+    tick: u64,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.inner.next() {
-                Some(next) => {
-                    if let Some(value) = next.as_ref() {
-                        return Some(unsafe { (*(value.get())).downcast_ref::<C>().unwrap() });
-                    }
-                }
-                None => return None,
-            }
-        }
+impl<'a, C> ComponentMut<'a, C> {
+    /// Marks the component as changed and returns the reference
+    #[inline]
+    pub fn take(self) -> &'a mut C {
+        self.ticks.mark_changed(self.tick);
+        self.component
     }
 }
 
+/// Iterator over mutable borrows of components of a column
 pub struct IterMut<'a, C> {
-    inner: std::slice::Iter<'a, Option<UnsafeCell<Box<dyn Any>>>>,
-    _phantom_data: PhantomData<C>,
+    inner: std::slice::IterMut<'a, C>,
+    ticks: std::slice::Iter<'a, TicksCell>,
+    tick: u64,
 }
 
 impl<'a, C: Any> Iterator for IterMut<'a, C> {
-    type Item = &'a mut C;
+    type Item = ComponentMut<'a, C>;
 
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.inner.next() {
-                Some(next) => {
-                    if let Some(value) = next.as_ref() {
-                        return Some(
-                            (unsafe { &mut *(value.get()) })
-                                .downcast_mut::<C>()
-                                .unwrap(),
-                        );
-                    }
-                }
-                None => return None,
-            }
-        }
+        Some(ComponentMut {
+            component: self.inner.next()?,
+            ticks: self.ticks.next()?,
+            tick: self.tick,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
 }
 
-impl From<&Entity> for Container {
-    fn from(entity: &Entity) -> Self {
-        Self {
-            data: entity
-                .archetype()
-                .map(|&type_id| (type_id, Vec::with_capacity(1)))
-                .collect::<HashMap<_, _>>(),
-            removed: Vec::new(),
-            len: 0,
-        }
+pub struct TicksIter<'a> {
+    inner: std::slice::Iter<'a, TicksCell>,
+}
+
+impl<'a> Iterator for TicksIter<'a> {
+    type Item = ComponentTicks;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|ticks| ticks.get())
     }
 }
 
-/*
 #[cfg(test)]
 mod tests {
-    struct Item1(u32);
-    struct Item2(u32);
-    use crate::world::container::Container;
-    #[test]
-    fn mutability() {
-        let mut c = Container::new::<(Item1, Item2)>();
-        c.push::<Item1>(Item1(123));
-        c.push::<Item2>(Item2(666));
+    use std::rc::Rc;
 
-        for i in c.get_mut::<Item1>().unwrap() {
-            i.0 += 198;
-        }
+    use super::{Container, Entity, IntoEntity};
+    use crate::utils::Id;
 
-        for i in c.get::<Item1>().unwrap() {
-            assert_eq!(i.0, 321);
+    #[test]
+    fn columns_own_components() {
+        let counter = Rc::new(());
+        let archetype = <(Rc<()>, ())>::components().unwrap();
+        let mut container = Container::new(&archetype);
+        for _ in 0..10 {
+            (Rc::clone(&counter), ()).store(&mut container, Id::new(), 1);
         }
+        assert_eq!(Rc::strong_count(&counter), 11);
+
+        let (mut entity, moved) = container.remove(0);
+        assert!(moved.is_some());
+        assert!(entity.remove::<Rc<()>>().is_some());
+        assert_eq!(Rc::strong_count(&counter), 10);
+
+        container.store(
+            Entity::new((Rc::clone(&counter), ())).with(Id::<Entity>::new()),
+            2,
+        );
+        assert_eq!(container.slots(), 10);
+        assert_eq!(container.iter::<Rc<()>>().count(), 10);
+        assert_eq!(container.ticks::<()>(9).unwrap().added, 2);
+
+        drop(container);
+        assert_eq!(Rc::strong_count(&counter), 1);
     }
 }
-*/